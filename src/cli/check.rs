@@ -0,0 +1,94 @@
+use crate::{
+    cli::{err::CliError, parse},
+    net::Scheme,
+    srv::{Connection, HttpServer, HttpsServer},
+};
+use std::process::ExitCode;
+
+pub fn check(matches: &clap::ArgMatches) -> ExitCode {
+    log::set_max_level(log::LevelFilter::Off);
+    let problems = match problems(matches) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if problems.is_empty() {
+        println!("configuration ok");
+        ExitCode::SUCCESS
+    } else {
+        for problem in &problems {
+            eprintln!("{}", problem);
+        }
+        ExitCode::FAILURE
+    }
+}
+
+pub fn problems(matches: &clap::ArgMatches) -> Result<Vec<String>, CliError> {
+    let resolved = parse::resolve(matches)?;
+    let mut problems: Vec<String> = Vec::new();
+    for e in resolved.errors {
+        match e {
+            CliError::Multiple(v) => problems.extend(v.iter().map(|e| e.to_string())),
+            e => problems.push(e.to_string()),
+        }
+    }
+    let config = &resolved.config;
+    if let Some(v) = &config.https_key_passphrase {
+        v.take_env();
+    }
+    let failed = &resolved.failed;
+    for listener in &config.listen {
+        let errors = match listener.scheme {
+            Scheme::Http => HttpServer::<Connection>::check(config, listener),
+            Scheme::Https => HttpsServer::check(config, listener),
+        };
+        for (_, e) in errors
+            .iter()
+            .filter(|(options, _)| !options.iter().any(|v| failed.contains(v)))
+        {
+            let problem = format!("{}: {}", listener, e);
+            if !problems.contains(&problem) {
+                problems.push(problem);
+            }
+        }
+    }
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(name: &str) -> String {
+        format!("{}/tests/data/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn independent_failures_are_all_reported() {
+        let (cert, key, dir) = (data("leaf.pem"), data("rsa.pem"), data(""));
+        let matches = clap::App::new("twebd")
+            .args(&parse::args())
+            .get_matches_from(vec![
+                "twebd",
+                "--https",
+                "-d",
+                &dir,
+                "--https-cert",
+                &cert,
+                "--https-key",
+                &key,
+                "--client-auth",
+                "required",
+                "--client-ca",
+                "/nonexistent/twebd-ca.pem",
+            ]);
+        let problems = problems(&matches).unwrap();
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].contains("--client-ca /nonexistent/twebd-ca.pem"));
+        assert!(problems[1].contains("private key doesn't match the certificate"));
+        assert!(!problems.iter().any(|v| v.contains("requires a client-ca")));
+    }
+}
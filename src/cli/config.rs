@@ -0,0 +1,740 @@
+use crate::{
+    cli::{default, err::CliError},
+    net::{ListenAddr, Listener, Scheme},
+    srv::{Limits, PathAction, PathRoute, Timeouts, VirtualHost},
+    web::{ClientAuth, HttpStatus, Passphrase, TlsVersion},
+};
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    env, fmt, fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+pub const ENV_PREFIX: &str = "TWEBD_";
+pub const LISTS: &[&str] = &[
+    "address",
+    "listen",
+    "self-signed-hosts",
+    "tls-ciphers",
+    "tls-kx-groups",
+    "alpn",
+];
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Values {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Scalar {
+    Str(String),
+    Int(i64),
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct LimitsConfig {
+    pub max_header_size: Option<usize>,
+    pub max_body_size: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RouteConfig {
+    pub path: String,
+    pub root: Option<PathBuf>,
+    pub redirect: Option<String>,
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub client_cert: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct VirtualHostConfig {
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub root: PathBuf,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub default: bool,
+    pub https_cert: Option<PathBuf>,
+    pub https_key: Option<PathBuf>,
+    pub https_ocsp: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub error_pages: BTreeMap<String, PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<RouteConfig>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    pub address: Option<Values>,
+    pub port: Option<u16>,
+    pub listen: Option<Values>,
+    pub unix_mode: Option<Scalar>,
+    pub redirect_https: Option<bool>,
+    pub directory: Option<PathBuf>,
+    pub loglevel: Option<String>,
+    pub threads: Option<usize>,
+    pub reactors: Option<bool>,
+    pub max_connections: Option<usize>,
+    pub header_timeout: Option<u64>,
+    pub body_timeout: Option<u64>,
+    pub processing_timeout: Option<u64>,
+    pub write_timeout: Option<u64>,
+    pub keepalive_timeout: Option<u64>,
+    pub backlog: Option<u32>,
+    pub ipv6_only: Option<bool>,
+    pub no_reuse_address: Option<bool>,
+    pub nodelay: Option<bool>,
+    pub https: Option<bool>,
+    pub https_cert: Option<PathBuf>,
+    pub https_key: Option<PathBuf>,
+    pub https_key_passphrase: Option<String>,
+    pub https_ocsp: Option<PathBuf>,
+    pub https_self_signed: Option<bool>,
+    pub self_signed_hosts: Option<Values>,
+    pub self_signed_days: Option<u32>,
+    pub cert_reload_interval: Option<u64>,
+    pub tls_min_version: Option<String>,
+    pub tls_ciphers: Option<Values>,
+    pub tls_kx_groups: Option<Values>,
+    pub alpn: Option<Values>,
+    pub tls_tickets: Option<bool>,
+    pub tls_ticket_rotation: Option<u64>,
+    pub tls_session_cache: Option<usize>,
+    pub client_auth: Option<String>,
+    pub client_ca: Option<PathBuf>,
+    pub client_crl: Option<PathBuf>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub error_pages: BTreeMap<String, PathBuf>,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub routes: Vec<RouteConfig>,
+    #[serde(default)]
+    pub vhosts: Vec<VirtualHostConfig>,
+    #[serde(skip)]
+    pub path: PathBuf,
+    #[serde(skip)]
+    pub lines: BTreeMap<String, usize>,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> Result<ConfigFile, CliError> {
+        let buf = match fs::read_to_string(path) {
+            Ok(v) => v,
+            Err(e) => return Err(CliError::Config(format!("{}: {}", path.display(), e))),
+        };
+        let mut config: ConfigFile = match toml::from_str(&buf) {
+            Ok(v) => v,
+            Err(e) => return Err(CliError::Config(format!("{}: {}", path.display(), e))),
+        };
+        let spans: BTreeMap<String, toml::Spanned<toml::Value>> =
+            toml::from_str(&buf).unwrap_or_default();
+        config.lines = spans
+            .into_iter()
+            .map(|(key, v)| (key, buf[..v.span().start].matches('\n').count() + 1))
+            .collect();
+        config.path = path.to_path_buf();
+        Ok(config)
+    }
+    pub fn values(&self) -> BTreeMap<&'static str, Vec<String>> {
+        let path = |v: &Option<PathBuf>| v.as_ref().map(|v| self.relative(v).display().to_string());
+        let values = [
+            ("address", self.address.as_ref().map(Values::to_vec)),
+            ("port", one(self.port)),
+            ("listen", self.listen.as_ref().map(Values::to_vec)),
+            ("unix-mode", one(self.unix_mode.as_ref())),
+            ("redirect-https", one(self.redirect_https)),
+            ("directory", one(path(&self.directory))),
+            ("loglevel", one(self.loglevel.as_ref())),
+            ("threads", one(self.threads)),
+            ("reactors", one(self.reactors)),
+            ("max-connections", one(self.max_connections)),
+            ("header-timeout", one(self.header_timeout)),
+            ("body-timeout", one(self.body_timeout)),
+            ("processing-timeout", one(self.processing_timeout)),
+            ("write-timeout", one(self.write_timeout)),
+            ("keepalive-timeout", one(self.keepalive_timeout)),
+            ("backlog", one(self.backlog)),
+            ("ipv6-only", one(self.ipv6_only)),
+            ("no-reuse-address", one(self.no_reuse_address)),
+            ("nodelay", one(self.nodelay)),
+            ("https", one(self.https)),
+            ("https-cert", one(path(&self.https_cert))),
+            ("https-key", one(path(&self.https_key))),
+            (
+                "https-key-passphrase",
+                one(self.https_key_passphrase.as_ref()),
+            ),
+            ("https-ocsp", one(path(&self.https_ocsp))),
+            ("https-self-signed", one(self.https_self_signed)),
+            (
+                "self-signed-hosts",
+                self.self_signed_hosts.as_ref().map(Values::to_vec),
+            ),
+            ("self-signed-days", one(self.self_signed_days)),
+            ("cert-reload-interval", one(self.cert_reload_interval)),
+            ("tls-min-version", one(self.tls_min_version.as_ref())),
+            ("tls-ciphers", self.tls_ciphers.as_ref().map(Values::to_vec)),
+            (
+                "tls-kx-groups",
+                self.tls_kx_groups.as_ref().map(Values::to_vec),
+            ),
+            ("alpn", self.alpn.as_ref().map(Values::to_vec)),
+            ("tls-tickets", one(self.tls_tickets)),
+            ("tls-ticket-rotation", one(self.tls_ticket_rotation)),
+            ("tls-session-cache", one(self.tls_session_cache)),
+            ("client-auth", one(self.client_auth.as_ref())),
+            ("client-ca", one(path(&self.client_ca))),
+            ("client-crl", one(path(&self.client_crl))),
+        ];
+        values
+            .into_iter()
+            .filter_map(|(name, v)| v.map(|v| (name, v)))
+            .collect()
+    }
+    pub fn apply(&self, config: &mut ServerConfig) -> Result<(), CliError> {
+        if let Some(v) = self.limits.max_header_size {
+            config.limits.header = v;
+        }
+        if let Some(v) = self.limits.max_body_size {
+            config.limits.body = v;
+        }
+        for (key, value) in &self.headers {
+            config.headers.push((key.to_owned(), value.to_owned()));
+        }
+        for route in &self.routes {
+            config.routes.push(self.route(route)?);
+        }
+        config
+            .error_pages
+            .append(&mut self.pages(&self.error_pages)?);
+        for vhost in &self.vhosts {
+            config.vhosts.push(self.vhost(vhost)?);
+        }
+        if config.vhosts.iter().filter(|v| v.default).count() > 1 {
+            return Err(self.error(String::from("more than one default vhost")));
+        }
+        Ok(())
+    }
+    fn vhost(&self, vhost: &VirtualHostConfig) -> Result<VirtualHost, CliError> {
+        let mut names = vec![vhost.name.to_owned()];
+        names.extend(vhost.aliases.iter().cloned());
+        for name in &names {
+            let wildcard = name.strip_prefix("*.").unwrap_or(name);
+            if wildcard.is_empty() || wildcard.contains('*') {
+                return Err(self.error(format!(
+                    "vhost {}: wildcard names must look like *.example.com",
+                    name
+                )));
+            }
+        }
+        if vhost.https_key.is_some() && vhost.https_cert.is_none() {
+            return Err(self.error(format!(
+                "vhost {}: https-key requires https-cert",
+                vhost.name
+            )));
+        }
+        if vhost.https_ocsp.is_some() && vhost.https_cert.is_none() {
+            return Err(self.error(format!(
+                "vhost {}: https-ocsp requires https-cert",
+                vhost.name
+            )));
+        }
+        let mut routes = Vec::new();
+        for route in &vhost.routes {
+            routes.push(self.route(route)?);
+        }
+        Ok(VirtualHost {
+            names,
+            root: self.relative(&vhost.root),
+            default: vhost.default,
+            https_cert: vhost.https_cert.as_ref().map(|v| self.relative(v)),
+            https_key: vhost
+                .https_key
+                .as_ref()
+                .or(vhost.https_cert.as_ref())
+                .map(|v| self.relative(v)),
+            https_ocsp: vhost.https_ocsp.as_ref().map(|v| self.relative(v)),
+            headers: vhost.headers.clone().into_iter().collect(),
+            routes,
+            error_pages: self.pages(&vhost.error_pages)?,
+        })
+    }
+    fn pages(
+        &self,
+        pages: &BTreeMap<String, PathBuf>,
+    ) -> Result<Vec<(HttpStatus, PathBuf)>, CliError> {
+        let mut statuses = Vec::new();
+        for (status, path) in pages {
+            match status.parse::<u16>().map(HttpStatus::try_from) {
+                Ok(Ok(v)) if v as u16 >= 400 => statuses.push((v, self.relative(path))),
+                _ => {
+                    return Err(self.error(format!(
+                        "error page {}: expected an error status code",
+                        status
+                    )))
+                }
+            }
+        }
+        Ok(statuses)
+    }
+    fn route(&self, route: &RouteConfig) -> Result<PathRoute, CliError> {
+        let status = match route.status.map(HttpStatus::try_from) {
+            Some(Ok(v)) => Some(v),
+            Some(Err(v)) => {
+                return Err(self.error(format!("route {}: unknown status {}", route.path, v)))
+            }
+            None => None,
+        };
+        let action = match (&route.root, &route.redirect, status) {
+            (Some(v), None, None) => PathAction::Root(self.relative(v)),
+            (None, Some(v), status) => {
+                PathAction::Redirect(v.to_owned(), status.unwrap_or(HttpStatus::Found))
+            }
+            (None, None, Some(status)) => PathAction::Status(status),
+            _ => {
+                return Err(self.error(format!(
+                    "route {}: expected one of root, redirect or status",
+                    route.path
+                )))
+            }
+        };
+        if !route.path.starts_with('/') {
+            return Err(self.error(format!("route {}: path must start with /", route.path)));
+        }
+        Ok(PathRoute {
+            path: PathBuf::from(&route.path),
+            action,
+            client_cert: route.client_cert,
+        })
+    }
+    fn relative(&self, path: &Path) -> PathBuf {
+        match self.path.parent() {
+            Some(v) => v.join(path),
+            None => path.to_path_buf(),
+        }
+    }
+    fn error(&self, e: String) -> CliError {
+        CliError::Config(format!("{}: {}", self.path.display(), e))
+    }
+}
+
+impl Values {
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            Values::One(v) => vec![v.to_owned()],
+            Values::Many(v) => v.to_vec(),
+        }
+    }
+}
+
+impl std::fmt::Display for Scalar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scalar::Str(v) => write!(f, "{}", v),
+            Scalar::Int(v) => write!(f, "0o{:o}", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    pub loglevel: log::LevelFilter,
+    pub listen: Vec<Listener>,
+    pub redirect_https: bool,
+    pub directory: PathBuf,
+    pub threads: usize,
+    pub reactors: bool,
+    pub max_connections: usize,
+    pub backlog: u32,
+    pub reuse_address: bool,
+    pub ipv6_only: bool,
+    pub nodelay: bool,
+    pub unix_mode: Option<u32>,
+    pub https_cert: PathBuf,
+    pub https_key: PathBuf,
+    pub https_key_passphrase: Option<Passphrase>,
+    pub https_ocsp: Option<PathBuf>,
+    pub https_self_signed: bool,
+    pub self_signed_hosts: Vec<String>,
+    pub self_signed_days: u32,
+    pub cert_reload_interval: Duration,
+    pub tls_min_version: TlsVersion,
+    pub tls_ciphers: Vec<String>,
+    pub tls_kx_groups: Vec<String>,
+    pub alpn: Vec<String>,
+    pub tls_tickets: bool,
+    pub tls_ticket_rotation: Duration,
+    pub tls_session_cache: usize,
+    pub client_auth: ClientAuth,
+    pub client_ca: Option<PathBuf>,
+    pub client_crl: Option<PathBuf>,
+    pub timeouts: Timeouts,
+    pub limits: Limits,
+    pub headers: Vec<(String, String)>,
+    pub error_pages: Vec<(HttpStatus, PathBuf)>,
+    pub routes: Vec<PathRoute>,
+    pub vhosts: Vec<VirtualHost>,
+}
+
+impl ServerConfig {
+    pub fn https(&self) -> bool {
+        self.listen
+            .iter()
+            .any(|listener| listener.scheme == Scheme::Https)
+    }
+    pub fn reactors_for(&self, listener: &Listener) -> bool {
+        self.reactors && matches!(listener.address, ListenAddr::Inet(_))
+    }
+    pub fn redirect_port(&self, listener: &Listener) -> Option<u16> {
+        if !self.redirect_https || listener.scheme != Scheme::Http || listener.port().is_none() {
+            return None;
+        }
+        self.listen
+            .iter()
+            .filter(|v| v.scheme == Scheme::Https)
+            .find_map(|v| v.port())
+    }
+    pub fn to_toml(&self) -> Result<String, CliError> {
+        toml::to_string(self).map_err(|e| CliError::Config(e.to_string()))
+    }
+    pub fn diff(&self, other: &ServerConfig) -> Vec<String> {
+        let mut changed = Vec::new();
+        diff_tables("", &table(self), &table(other), &mut changed);
+        changed
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            loglevel: default::loglevel(),
+            listen: vec![Listener::new(
+                Scheme::Http,
+                SocketAddr::new(default::address(), default::port()),
+            )],
+            redirect_https: default::redirect_https(),
+            directory: default::directory(),
+            threads: default::threads(),
+            reactors: default::reactors(),
+            max_connections: default::max_connections(),
+            backlog: default::backlog(),
+            reuse_address: default::reuse_address(),
+            ipv6_only: default::ipv6_only(),
+            nodelay: default::nodelay(),
+            unix_mode: None,
+            https_cert: default::https_cert(),
+            https_key: default::https_priv_key(),
+            https_key_passphrase: None,
+            https_ocsp: None,
+            https_self_signed: default::https_self_signed(),
+            self_signed_hosts: default::self_signed_hosts(),
+            self_signed_days: default::self_signed_days(),
+            cert_reload_interval: default::cert_reload_interval(),
+            tls_min_version: default::tls_min_version(),
+            tls_ciphers: Vec::new(),
+            tls_kx_groups: Vec::new(),
+            alpn: default::alpn(),
+            tls_tickets: default::tls_tickets(),
+            tls_ticket_rotation: default::tls_ticket_rotation(),
+            tls_session_cache: default::tls_session_cache(),
+            client_auth: default::client_auth(),
+            client_ca: None,
+            client_crl: None,
+            timeouts: Timeouts {
+                header: default::header_timeout(),
+                body: default::body_timeout(),
+                processing: default::processing_timeout(),
+                write: default::write_timeout(),
+                idle: default::idle_timeout(),
+            },
+            limits: Limits {
+                header: default::max_header_size(),
+                body: default::max_body_size(),
+            },
+            headers: Vec::new(),
+            error_pages: Vec::new(),
+            routes: Vec::new(),
+            vhosts: Vec::new(),
+        }
+    }
+}
+
+impl Serialize for ServerConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ConfigFile::from(self).serialize(serializer)
+    }
+}
+
+impl From<&ServerConfig> for ConfigFile {
+    fn from(config: &ServerConfig) -> ConfigFile {
+        let path = |v: &Path| Some(v.to_path_buf());
+        ConfigFile {
+            listen: Some(Values::Many(
+                config.listen.iter().map(|v| v.to_string()).collect(),
+            )),
+            unix_mode: config.unix_mode.map(|v| Scalar::Str(format!("{:o}", v))),
+            redirect_https: Some(config.redirect_https),
+            directory: path(&config.directory),
+            loglevel: Some(config.loglevel.to_string().to_ascii_lowercase()),
+            threads: Some(config.threads),
+            reactors: Some(config.reactors),
+            max_connections: Some(config.max_connections),
+            header_timeout: Some(config.timeouts.header.as_secs()),
+            body_timeout: Some(config.timeouts.body.as_secs()),
+            processing_timeout: Some(config.timeouts.processing.as_secs()),
+            write_timeout: Some(config.timeouts.write.as_secs()),
+            keepalive_timeout: Some(config.timeouts.idle.as_secs()),
+            backlog: Some(config.backlog),
+            ipv6_only: Some(config.ipv6_only),
+            no_reuse_address: Some(!config.reuse_address),
+            nodelay: Some(config.nodelay),
+            https_cert: path(&config.https_cert),
+            https_key: path(&config.https_key),
+            https_key_passphrase: config.https_key_passphrase.as_ref().map(|v| v.to_string()),
+            https_ocsp: config.https_ocsp.clone(),
+            https_self_signed: Some(config.https_self_signed),
+            self_signed_hosts: Some(Values::Many(config.self_signed_hosts.to_vec())),
+            self_signed_days: Some(config.self_signed_days),
+            cert_reload_interval: Some(config.cert_reload_interval.as_secs()),
+            tls_min_version: Some(config.tls_min_version.to_string()),
+            tls_ciphers: many(&config.tls_ciphers),
+            tls_kx_groups: many(&config.tls_kx_groups),
+            alpn: Some(Values::Many(config.alpn.to_vec())),
+            tls_tickets: Some(config.tls_tickets),
+            tls_ticket_rotation: Some(config.tls_ticket_rotation.as_secs()),
+            tls_session_cache: Some(config.tls_session_cache),
+            client_auth: Some(config.client_auth.to_string()),
+            client_ca: config.client_ca.clone(),
+            client_crl: config.client_crl.clone(),
+            headers: config.headers.iter().cloned().collect(),
+            error_pages: pages(&config.error_pages),
+            limits: LimitsConfig {
+                max_header_size: Some(config.limits.header),
+                max_body_size: Some(config.limits.body),
+            },
+            routes: config.routes.iter().map(RouteConfig::from).collect(),
+            vhosts: config.vhosts.iter().map(VirtualHostConfig::from).collect(),
+            ..ConfigFile::default()
+        }
+    }
+}
+
+impl From<&PathRoute> for RouteConfig {
+    fn from(route: &PathRoute) -> RouteConfig {
+        let mut config = RouteConfig {
+            path: route.path.display().to_string(),
+            root: None,
+            redirect: None,
+            status: None,
+            client_cert: route.client_cert,
+        };
+        match &route.action {
+            PathAction::Root(v) => config.root = Some(v.to_path_buf()),
+            PathAction::Redirect(v, status) => {
+                config.redirect = Some(v.to_owned());
+                config.status = Some(*status as u16);
+            }
+            PathAction::Status(status) => config.status = Some(*status as u16),
+        }
+        config
+    }
+}
+
+impl From<&VirtualHost> for VirtualHostConfig {
+    fn from(vhost: &VirtualHost) -> VirtualHostConfig {
+        VirtualHostConfig {
+            name: vhost.names.first().cloned().unwrap_or_default(),
+            aliases: vhost.names.iter().skip(1).cloned().collect(),
+            root: vhost.root.to_path_buf(),
+            default: vhost.default,
+            https_cert: vhost.https_cert.clone(),
+            https_key: vhost.https_key.clone(),
+            https_ocsp: vhost.https_ocsp.clone(),
+            headers: vhost.headers.iter().cloned().collect(),
+            error_pages: pages(&vhost.error_pages),
+            routes: vhost.routes.iter().map(RouteConfig::from).collect(),
+        }
+    }
+}
+
+fn pages(pages: &[(HttpStatus, PathBuf)]) -> BTreeMap<String, PathBuf> {
+    pages
+        .iter()
+        .map(|(status, path)| ((*status as u16).to_string(), path.to_path_buf()))
+        .collect()
+}
+
+fn table(config: &ServerConfig) -> toml::Table {
+    toml::Table::try_from(config).unwrap_or_default()
+}
+
+fn diff_tables(prefix: &str, a: &toml::Table, b: &toml::Table, changed: &mut Vec<String>) {
+    let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    for key in keys {
+        let name = format!("{}{}", prefix, key);
+        match (a.get(key), b.get(key)) {
+            (Some(toml::Value::Table(a)), Some(toml::Value::Table(b))) => {
+                diff_tables(&format!("{}.", name), a, b, changed)
+            }
+            (a, b) if a != b => changed.push(name),
+            _ => {}
+        }
+    }
+}
+
+pub enum Origin<'a> {
+    Arg(&'a str),
+    Env(&'a str),
+    File(&'a Path, usize, &'a str),
+}
+
+impl fmt::Display for Origin<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Arg(name) => write!(f, "--{}", name),
+            Self::Env(name) => write!(f, "{}", env_name(name)),
+            Self::File(path, line, name) => write!(f, "{}:{}: {}", path.display(), line, name),
+        }
+    }
+}
+
+pub struct Settings<'a> {
+    matches: &'a clap::ArgMatches<'a>,
+    file: BTreeMap<&'static str, Vec<String>>,
+    config: Option<&'a ConfigFile>,
+}
+
+impl<'a> Settings<'a> {
+    pub fn new(matches: &'a clap::ArgMatches<'a>, config: Option<&'a ConfigFile>) -> Settings<'a> {
+        Settings {
+            matches,
+            file: config.map(|v| v.values()).unwrap_or_default(),
+            config,
+        }
+    }
+    pub fn origin<'b>(&'b self, name: &'b str) -> Origin<'b> {
+        if self.matches.occurrences_of(name) > 0 {
+            return Origin::Arg(name);
+        }
+        if env_var(name).is_some() {
+            return Origin::Env(name);
+        }
+        match self.config {
+            Some(config) if self.file.contains_key(name) => Origin::File(
+                &config.path,
+                config.lines.get(name).copied().unwrap_or_default(),
+                name,
+            ),
+            _ => Origin::Arg(name),
+        }
+    }
+    pub fn error(&self, name: &str, value: &str, e: impl fmt::Display) -> CliError {
+        match self.origin(name) {
+            Origin::Env(name) => CliError::Parse(format!("{}={}: {}", env_name(name), value, e)),
+            v @ Origin::File(..) => CliError::Config(format!("{} {}: {}", v, value, e)),
+            v => CliError::Parse(format!("{} {}: {}", v, value, e)),
+        }
+    }
+    pub fn value_of(&self, name: &str) -> Option<String> {
+        self.values_of(name).and_then(|v| v.into_iter().last())
+    }
+    pub fn values_of(&self, name: &str) -> Option<Vec<String>> {
+        if self.matches.occurrences_of(name) > 0 {
+            return self
+                .matches
+                .values_of(name)
+                .map(|v| v.map(String::from).collect());
+        }
+        if let Some(v) = env_var(name) {
+            if !LISTS.contains(&name) {
+                return Some(vec![v]);
+            }
+            return Some(
+                v.split(',')
+                    .map(|v| v.trim().to_owned())
+                    .filter(|v| !v.is_empty())
+                    .collect(),
+            );
+        }
+        self.file.get(name).cloned()
+    }
+    pub fn is_present(&self, name: &str) -> bool {
+        if self.matches.is_present(name) {
+            return true;
+        }
+        if let Some(v) = env_var(name) {
+            return matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on");
+        }
+        match self.file.get(name) {
+            Some(v) => v.iter().any(|v| v == "true"),
+            None => false,
+        }
+    }
+}
+
+fn many(v: &[String]) -> Option<Values> {
+    match v.is_empty() {
+        true => None,
+        false => Some(Values::Many(v.to_vec())),
+    }
+}
+
+fn one<T: ToString>(v: Option<T>) -> Option<Vec<String>> {
+    v.map(|v| vec![v.to_string()])
+}
+
+pub fn env_name(name: &str) -> String {
+    format!(
+        "{}{}",
+        ENV_PREFIX,
+        name.to_ascii_uppercase().replace('-', "_")
+    )
+}
+
+fn env_var(name: &str) -> Option<String> {
+    env::var(env_name(name)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lists_changed_keys() {
+        let config = ServerConfig::default();
+        assert_eq!(config.diff(&config.clone()), Vec::<String>::new());
+
+        let mut other = config.clone();
+        other.threads = config.threads + 1;
+        other.timeouts.idle = Duration::from_secs(60);
+        other.limits.body = 1;
+        other.headers = vec![(String::from("X-Frame-Options"), String::from("DENY"))];
+        assert_eq!(
+            config.diff(&other),
+            vec![
+                "headers.X-Frame-Options",
+                "keepalive-timeout",
+                "limits.max-body-size",
+                "threads"
+            ]
+        );
+        assert_eq!(other.diff(&config), config.diff(&other));
+    }
+}
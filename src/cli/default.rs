@@ -7,6 +7,22 @@ pub fn port() -> u16 {
     8080
 }
 
+pub fn backlog() -> u32 {
+    1024
+}
+
+pub fn reuse_address() -> bool {
+    true
+}
+
+pub fn ipv6_only() -> bool {
+    false
+}
+
+pub fn nodelay() -> bool {
+    false
+}
+
 pub fn protocol() -> crate::net::DataProtocol {
     crate::net::DataProtocol::Tcp
 }
@@ -27,10 +43,74 @@ pub fn threads_max() -> usize {
     10
 }
 
+pub fn max_connections() -> usize {
+    1024
+}
+
+pub fn max_header_size() -> usize {
+    16 * 1024
+}
+
+pub fn max_body_size() -> usize {
+    1024 * 1024
+}
+
+pub fn header_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(10)
+}
+
+pub fn body_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(30)
+}
+
+pub fn processing_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(60)
+}
+
+pub fn write_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(30)
+}
+
+pub fn idle_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(5)
+}
+
+pub fn reactors() -> bool {
+    false
+}
+
 pub fn https() -> bool {
     false
 }
 
+pub fn redirect_https() -> bool {
+    false
+}
+
+pub fn cert_reload_interval() -> std::time::Duration {
+    std::time::Duration::from_secs(5)
+}
+
+pub fn tls_min_version() -> crate::web::TlsVersion {
+    crate::web::TlsVersion::Tls12
+}
+
+pub fn alpn() -> Vec<String> {
+    vec![String::from("http/1.1")]
+}
+
+pub fn tls_tickets() -> bool {
+    false
+}
+
+pub fn tls_ticket_rotation() -> std::time::Duration {
+    std::time::Duration::from_secs(6 * 60 * 60)
+}
+
+pub fn tls_session_cache() -> usize {
+    256
+}
+
 pub fn https_cert() -> std::path::PathBuf {
     std::path::PathBuf::from("./ssl/localhost.crt")
 }
@@ -38,3 +118,27 @@ pub fn https_cert() -> std::path::PathBuf {
 pub fn https_priv_key() -> std::path::PathBuf {
     std::path::PathBuf::from("./ssl/localhost.key")
 }
+
+pub fn https_self_signed() -> bool {
+    false
+}
+
+pub fn self_signed_hosts() -> Vec<String> {
+    vec![
+        String::from("localhost"),
+        String::from("127.0.0.1"),
+        String::from("::1"),
+    ]
+}
+
+pub fn self_signed_days() -> u32 {
+    365
+}
+
+pub fn self_signed_days_max() -> u32 {
+    36500
+}
+
+pub fn client_auth() -> crate::web::ClientAuth {
+    crate::web::ClientAuth::None
+}
@@ -1,4 +1,21 @@
+use std::fmt;
+
 #[derive(Debug)]
 pub enum CliError {
     Parse(String),
+    Config(String),
+    Multiple(Vec<CliError>),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(v) => write!(f, "invalid arguments: {}", v),
+            Self::Config(v) => write!(f, "invalid configuration: {}", v),
+            Self::Multiple(v) => {
+                let errors: Vec<String> = v.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", errors.join("\n"))
+            }
+        }
+    }
 }
@@ -0,0 +1,87 @@
+use crate::{cli::default, web::SelfSigned};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+pub fn gen_cert(matches: &clap::ArgMatches) -> ExitCode {
+    log::set_max_level(log::LevelFilter::Off);
+    let hosts: Vec<String> = match matches.values_of("hosts") {
+        Some(v) => v
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(String::from)
+            .collect(),
+        None => default::self_signed_hosts(),
+    };
+    let days = match matches.value_of("days").map(str::parse::<u32>) {
+        Some(Ok(v)) if v == 0 || v > default::self_signed_days_max() => {
+            return fail(&format!(
+                "--days {}: must be between 1 and {}",
+                v,
+                default::self_signed_days_max()
+            ))
+        }
+        Some(Ok(v)) => v,
+        Some(Err(e)) => return fail(&format!("--days: {}", e)),
+        None => default::self_signed_days(),
+    };
+    let cert_path = matches
+        .value_of("cert")
+        .map(PathBuf::from)
+        .unwrap_or_else(default::https_cert);
+    let key_path = matches
+        .value_of("key")
+        .map(PathBuf::from)
+        .unwrap_or_else(default::https_priv_key);
+    if !matches.is_present("force") {
+        for path in [&cert_path, &key_path] {
+            if path.exists() {
+                return fail(&format!(
+                    "{} already exists, use --force to overwrite it",
+                    path.display()
+                ));
+            }
+        }
+    }
+
+    let cert = match SelfSigned::generate(&hosts, days) {
+        Ok(v) => v,
+        Err(e) => return fail(&e.to_string()),
+    };
+    if let Err(e) = write(&cert_path, &cert.cert_pem(), 0o644) {
+        return fail(&format!("{}: {}", cert_path.display(), e));
+    }
+    if let Err(e) = write(&key_path, &cert.key_pem(), 0o600) {
+        return fail(&format!("{}: {}", key_path.display(), e));
+    }
+    println!("certificate: {}", cert_path.display());
+    println!("private key: {}", key_path.display());
+    println!("hosts: {}", cert.hosts.join(", "));
+    println!("valid until: {}", cert.not_after.format("%Y-%m-%d"));
+    println!("sha256 fingerprint: {}", cert.fingerprint());
+    ExitCode::SUCCESS
+}
+
+fn write(path: &Path, contents: &str, mode: u32) -> io::Result<()> {
+    if let Some(v) = path.parent().filter(|v| !v.as_os_str().is_empty()) {
+        fs::create_dir_all(v)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, mode);
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(mode))?;
+    #[cfg(not(unix))]
+    let _ = mode;
+    file.write_all(contents.as_bytes())
+}
+
+fn fail(message: &str) -> ExitCode {
+    eprintln!("{}", message);
+    ExitCode::FAILURE
+}
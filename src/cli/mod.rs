@@ -1,28 +1,16 @@
+pub mod check;
+pub mod config;
 pub mod default;
 pub mod err;
+pub mod gen_cert;
 pub mod parse;
 pub mod run;
 
-pub use parse::{parse_args, parse_matches, CliConfig};
+pub use config::ServerConfig;
+pub use parse::{parse_args, parse_matches};
 pub use run::run;
 
-use crate::net::DataProtocol;
-use std::{net::IpAddr, path::PathBuf};
-
-#[derive(Debug, Clone)]
-pub enum CliOpt {
-    Address(IpAddr),
-    Port(u16),
-    Protocol(DataProtocol),
-    Directory(PathBuf),
-    Verbosity(log::LevelFilter),
-    Threads(usize),
-    ShowTimestamp(bool),
-    ShowLoglevel(bool),
-    Https(bool),
-    HttpsCert(Option<PathBuf>),
-    HttpsPrivKey(Option<PathBuf>),
-}
+use crate::net::Listener;
 
 pub trait Builder<T, V, E>
 where
@@ -30,8 +18,9 @@ where
     V: Sized,
     E: Sized,
 {
-    fn new(opts: Vec<CliOpt>) -> T;
+    fn new(config: &ServerConfig, listener: &Listener) -> T;
     fn build(&self) -> std::result::Result<V, E>;
-    fn add_other(&mut self, o: CliOpt);
-    fn other(&self) -> Vec<CliOpt>;
+    fn check(&self) -> std::result::Result<(), E> {
+        self.build().map(|_| ())
+    }
 }
@@ -1,188 +1,757 @@
+#[cfg(unix)]
+use crate::net::systemd;
 use crate::{
-    cli::{default, err::CliError, CliOpt},
+    cli::{
+        config::{env_name, ConfigFile, Origin, ServerConfig, Settings},
+        default,
+        err::CliError,
+    },
+    net::{Listener, Scheme},
+    web::{
+        https::params::{alpn_protocol, cipher_suite, kx_group},
+        ClientAuth, Passphrase, TlsVersion,
+    },
     APP_AUTHOR, APP_DESCRIPTION, APP_NAME, APP_VERSION,
 };
-use clap::{App, Arg};
-use log::{error, warn};
-use std::{net::IpAddr, path::PathBuf, result::Result, str::FromStr};
-
-pub struct CliConfig {
-    cli_opts: Vec<CliOpt>,
-}
-
-impl CliConfig {
-    pub fn log_level(&self) -> log::LevelFilter {
-        for opt in &self.cli_opts {
-            match opt {
-                CliOpt::Verbosity(v) => return *v,
-                _ => {}
-            }
-        }
-        log::LevelFilter::Info
-    }
-    pub fn https(&self) -> bool {
-        for opt in &self.cli_opts {
-            match opt {
-                CliOpt::Https(v) => return *v,
-                _ => {}
-            }
-        }
-        false
-    }
-    pub fn cli_opts(&self) -> Vec<CliOpt> {
-        self.cli_opts.to_vec()
-    }
-}
+use clap::{App, Arg, SubCommand};
+use log::{error, info, warn};
+use std::{
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    result::Result,
+    str::FromStr,
+    time::Duration,
+};
 
 pub fn parse_args<'a>() -> clap::ArgMatches<'a> {
     App::new(APP_NAME)
         .version(APP_VERSION)
         .author(APP_AUTHOR)
         .about(APP_DESCRIPTION)
+        .args(&args())
         .arg(
-            Arg::with_name("address")
-                .short("a")
-                .long("address")
-                .required(false)
-                .takes_value(true)
-                .value_name("IP")
-                .max_values(1)
-                .long_help("Sets the server IP (v4/v6) address"),
-        )
-        .arg(
-            Arg::with_name("port")
-                .short("p")
-                .long("port")
-                .required(false)
-                .takes_value(true)
-                .value_name("PORT")
-                .max_values(1)
-                .long_help("Sets the server port number [possible values: 1..65535]"),
-        )
-        .arg(
-            Arg::with_name("directory")
-                .short("d")
-                .long("directory")
-                .required(false)
-                .takes_value(true)
-                .value_name("ROOT_PATH")
-                .max_values(1)
-                .long_help("Sets the server root/public_html/wwwroot directory"),
-        )
-        .arg(
-            Arg::with_name("loglevel")
-                .short("l")
-                .long("loglevel")
-                .required(false)
-                .takes_value(true)
-                .possible_values(&["error", "warn", "info", "debug", "trace"])
-                .value_name("LOG_LEVEL")
-                .max_values(1)
-                .long_help("Sets the server logging verbosity"),
-        )
-        .arg(
-            Arg::with_name("threads")
-                .short("t")
-                .long("threads")
-                .required(false)
-                .takes_value(true)
-                .value_name("N_THREADS")
-                .max_values(1)
-                .long_help(
-                    "Sets the number of threads used by the server [possible values: 1..10]",
-                ),
-        )
-        .arg(
-            Arg::with_name("https")
-                .short("s")
-                .long("https")
+            Arg::with_name("print-config")
+                .long("print-config")
                 .required(false)
-                .requires_all(&["https-cert", "https-key"])
                 .takes_value(false)
-                .long_help("Use https, requires a certificate and private key"),
+                .long_help("Prints the resolved configuration as TOML and exits"),
         )
-        .arg(
-            Arg::with_name("https-cert")
-                .short("c")
-                .long("https-cert")
-                .requires("https")
-                .takes_value(true)
-                .value_name("CERT_PATH")
-                .max_values(1)
-                .long_help("Path to the server certificate file"),
+        .subcommand(
+            SubCommand::with_name("check")
+                .about(
+                    "Validates the configuration without binding sockets, reporting every problem",
+                )
+                .args(&args()),
         )
-        .arg(
-            Arg::with_name("https-key")
-                .short("k")
-                .long("https-key")
-                .requires("https")
-                .takes_value(true)
-                .value_name("KEY_PATH")
-                .max_values(1)
-                .long_help("Path to the server private key file"),
+        .subcommand(
+            SubCommand::with_name("gen-cert")
+                .about("Generates a self-signed certificate and private key for development")
+                .args(&gen_cert_args()),
         )
         .get_matches()
 }
 
-pub fn parse_matches(matches: &clap::ArgMatches) -> Result<CliConfig, CliError> {
-    let cli_opts = vec![
-        loglevel(matches)?,
-        https(matches)?,
-        address(matches)?,
-        port(matches)?,
-        directory(matches)?,
-        threads(matches)?,
-    ];
+pub fn args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("config")
+            .long("config")
+            .required(false)
+            .takes_value(true)
+            .value_name("CONFIG_PATH")
+            .max_values(1)
+            .long_help(
+                "Path to a TOML configuration file. Settings are taken from command line flags, \
+                 then TWEBD_* environment variables, then the configuration file, then defaults",
+            ),
+        Arg::with_name("address")
+            .short("a")
+            .long("address")
+            .required(false)
+            .takes_value(true)
+            .value_name("IP")
+            .multiple(true)
+            .number_of_values(1)
+            .long_help("Sets the server IP (v4/v6) address, may be repeated"),
+        Arg::with_name("port")
+            .short("p")
+            .long("port")
+            .required(false)
+            .takes_value(true)
+            .value_name("PORT")
+            .max_values(1)
+            .long_help("Sets the server port number [possible values: 1..65535]"),
+        Arg::with_name("listen")
+            .long("listen")
+            .required(false)
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("SCHEME://IP:PORT")
+            .long_help(
+                "Adds a listener, e.g. http://0.0.0.0:80, https://[::]:443, [::1]:8080 or \
+                 unix:/run/twebd.sock, may be repeated. Overrides the address, port and https \
+                 options",
+            ),
+        Arg::with_name("unix-mode")
+            .long("unix-mode")
+            .required(false)
+            .takes_value(true)
+            .value_name("MODE")
+            .max_values(1)
+            .long_help("Sets the file permissions of unix socket listeners, in octal, e.g. 660"),
+        Arg::with_name("backlog")
+            .long("backlog")
+            .required(false)
+            .takes_value(true)
+            .value_name("N")
+            .max_values(1)
+            .long_help("Sets the length of the pending connection queue of tcp listeners"),
+        Arg::with_name("ipv6-only")
+            .long("ipv6-only")
+            .required(false)
+            .takes_value(false)
+            .long_help(
+                "Sets IPV6_V6ONLY on ipv6 listeners, so [::] doesn't also accept ipv4 \
+                 connections. Without it, ipv6 listeners are always dual-stack",
+            ),
+        Arg::with_name("no-reuse-address")
+            .long("no-reuse-address")
+            .required(false)
+            .takes_value(false)
+            .long_help("Don't set SO_REUSEADDR on tcp listeners"),
+        Arg::with_name("nodelay")
+            .long("nodelay")
+            .required(false)
+            .takes_value(false)
+            .long_help("Sets TCP_NODELAY on accepted connections, disabling Nagle's algorithm"),
+        Arg::with_name("redirect-https")
+            .long("redirect-https")
+            .required(false)
+            .takes_value(false)
+            .long_help("Redirect requests on http listeners to the first https listener"),
+        Arg::with_name("directory")
+            .short("d")
+            .long("directory")
+            .required(false)
+            .takes_value(true)
+            .value_name("ROOT_PATH")
+            .max_values(1)
+            .long_help("Sets the server root/public_html/wwwroot directory"),
+        Arg::with_name("loglevel")
+            .short("l")
+            .long("loglevel")
+            .required(false)
+            .takes_value(true)
+            .possible_values(&["error", "warn", "info", "debug", "trace"])
+            .value_name("LOG_LEVEL")
+            .max_values(1)
+            .long_help("Sets the server logging verbosity"),
+        Arg::with_name("threads")
+            .short("t")
+            .long("threads")
+            .required(false)
+            .takes_value(true)
+            .value_name("N_THREADS")
+            .max_values(1)
+            .long_help("Sets the number of threads used by the server [possible values: 1..10]"),
+        Arg::with_name("max-connections")
+            .short("m")
+            .long("max-connections")
+            .required(false)
+            .takes_value(true)
+            .value_name("N_CONNECTIONS")
+            .max_values(1)
+            .long_help("Sets the maximum number of open connections per event loop"),
+        Arg::with_name("header-timeout")
+            .long("header-timeout")
+            .required(false)
+            .takes_value(true)
+            .value_name("SECONDS")
+            .max_values(1)
+            .long_help("Sets the time allowed for receiving request headers, in seconds"),
+        Arg::with_name("body-timeout")
+            .long("body-timeout")
+            .required(false)
+            .takes_value(true)
+            .value_name("SECONDS")
+            .max_values(1)
+            .long_help("Sets the time allowed for receiving a request body, in seconds"),
+        Arg::with_name("processing-timeout")
+            .long("processing-timeout")
+            .required(false)
+            .takes_value(true)
+            .value_name("SECONDS")
+            .max_values(1)
+            .long_help("Sets the time allowed for producing a response before replying 504 and closing, in seconds"),
+        Arg::with_name("write-timeout")
+            .long("write-timeout")
+            .required(false)
+            .takes_value(true)
+            .value_name("SECONDS")
+            .max_values(1)
+            .long_help("Sets the time allowed for a stalled response write, in seconds"),
+        Arg::with_name("keepalive-timeout")
+            .long("keepalive-timeout")
+            .required(false)
+            .takes_value(true)
+            .value_name("SECONDS")
+            .max_values(1)
+            .long_help("Sets the time an idle keep-alive connection is kept open, in seconds"),
+        Arg::with_name("reactors")
+            .short("r")
+            .long("reactors")
+            .required(false)
+            .takes_value(false)
+            .long_help("Run one event loop per thread, each with its own SO_REUSEPORT listener"),
+        Arg::with_name("https")
+            .short("s")
+            .long("https")
+            .required(false)
+            .takes_value(false)
+            .long_help("Use https with the --https-cert certificate and --https-key private key"),
+        Arg::with_name("https-cert")
+            .short("c")
+            .long("https-cert")
+            .takes_value(true)
+            .value_name("CERT_PATH")
+            .max_values(1)
+            .long_help("Path to the server certificate file"),
+        Arg::with_name("https-key")
+            .short("k")
+            .long("https-key")
+            .takes_value(true)
+            .value_name("KEY_PATH")
+            .max_values(1)
+            .long_help("Path to the server private key file"),
+        Arg::with_name("https-key-passphrase")
+            .long("https-key-passphrase")
+            .takes_value(true)
+            .value_name("SOURCE")
+            .max_values(1)
+            .long_help(
+                "Where to read the passphrase of encrypted private keys from: env:NAME, \
+                 fd:NUMBER, file:PATH or prompt",
+            ),
+        Arg::with_name("https-ocsp")
+            .long("https-ocsp")
+            .takes_value(true)
+            .value_name("OCSP_PATH")
+            .max_values(1)
+            .long_help(
+                "Path to a DER OCSP response for the server certificate, stapled while it is \
+                 valid and reloaded when the file changes",
+            ),
+        Arg::with_name("https-self-signed")
+            .long("https-self-signed")
+            .required(false)
+            .takes_value(false)
+            .long_help(
+                "Use https with a self-signed certificate generated in memory at startup, \
+                 instead of --https-cert and --https-key. For development only",
+            ),
+        Arg::with_name("self-signed-hosts")
+            .long("self-signed-hosts")
+            .takes_value(true)
+            .value_name("HOST")
+            .multiple(true)
+            .number_of_values(1)
+            .long_help(
+                "Sets the host names and IP addresses of the self-signed certificate \
+                 [default: localhost, 127.0.0.1, ::1], may be repeated",
+            ),
+        Arg::with_name("self-signed-days")
+            .long("self-signed-days")
+            .takes_value(true)
+            .value_name("DAYS")
+            .max_values(1)
+            .long_help("Sets how many days the self-signed certificate is valid for [possible values: 1..36500]"),
+        Arg::with_name("cert-reload-interval")
+            .long("cert-reload-interval")
+            .takes_value(true)
+            .value_name("SECONDS")
+            .max_values(1)
+            .long_help(
+                "Sets how often certificate and key files are checked for changes, in seconds. \
+                 Changed files are loaded for new connections",
+            ),
+        Arg::with_name("tls-min-version")
+            .long("tls-min-version")
+            .takes_value(true)
+            .value_name("VERSION")
+            .max_values(1)
+            .long_help("Sets the minimum tls protocol version [possible values: 1.2, 1.3]"),
+        Arg::with_name("tls-ciphers")
+            .long("tls-ciphers")
+            .takes_value(true)
+            .value_name("SUITE")
+            .multiple(true)
+            .number_of_values(1)
+            .long_help(
+                "Restricts the tls cipher suites, in order of preference, e.g. \
+                 TLS13_AES_256_GCM_SHA384, may be repeated",
+            ),
+        Arg::with_name("tls-kx-groups")
+            .long("tls-kx-groups")
+            .takes_value(true)
+            .value_name("GROUP")
+            .multiple(true)
+            .number_of_values(1)
+            .long_help(
+                "Restricts the tls key exchange groups, in order of preference \
+                 [possible values: X25519, secp256r1, secp384r1], may be repeated",
+            ),
+        Arg::with_name("alpn")
+            .long("alpn")
+            .takes_value(true)
+            .value_name("PROTOCOL")
+            .multiple(true)
+            .number_of_values(1)
+            .long_help(
+                "Sets the protocols offered with ALPN on https listeners, in order of preference \
+                 [default: http/1.1] [possible values: http/1.1, http/1.0], may be repeated",
+            ),
+        Arg::with_name("tls-tickets")
+            .long("tls-tickets")
+            .required(false)
+            .takes_value(false)
+            .long_help("Issues stateless tls session tickets, so clients can resume sessions"),
+        Arg::with_name("tls-ticket-rotation")
+            .long("tls-ticket-rotation")
+            .takes_value(true)
+            .value_name("SECONDS")
+            .max_values(1)
+            .long_help(
+                "Sets how often the session ticket key is replaced, in seconds. This is also \
+                 the ticket lifetime advertised to clients",
+            ),
+        Arg::with_name("tls-session-cache")
+            .long("tls-session-cache")
+            .takes_value(true)
+            .value_name("N")
+            .max_values(1)
+            .long_help("Sets the number of tls sessions kept for resumption, 0 disables the cache"),
+        Arg::with_name("client-auth")
+            .long("client-auth")
+            .takes_value(true)
+            .value_name("MODE")
+            .max_values(1)
+            .long_help(
+                "Requests a client certificate on https listeners [possible values: none, \
+                 optional, required]. Certificates are verified against --client-ca",
+            ),
+        Arg::with_name("client-ca")
+            .long("client-ca")
+            .takes_value(true)
+            .value_name("CA_PATH")
+            .max_values(1)
+            .long_help("Path to the CA bundle that client certificates are verified against"),
+        Arg::with_name("client-crl")
+            .long("client-crl")
+            .takes_value(true)
+            .value_name("CRL_PATH")
+            .max_values(1)
+            .long_help("Path to certificate revocation lists, signed by a --client-ca certificate"),
+    ]
+}
+
+fn gen_cert_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("hosts")
+            .long("hosts")
+            .takes_value(true)
+            .value_name("HOSTS")
+            .multiple(true)
+            .use_delimiter(true)
+            .long_help(
+                "Sets the host names and IP addresses of the certificate, comma separated \
+                 [default: localhost,127.0.0.1,::1]",
+            ),
+        Arg::with_name("days")
+            .long("days")
+            .takes_value(true)
+            .value_name("DAYS")
+            .max_values(1)
+            .long_help("Sets how many days the certificate is valid for [default: 365] [possible values: 1..36500]"),
+        Arg::with_name("cert")
+            .long("cert")
+            .takes_value(true)
+            .value_name("CERT_PATH")
+            .max_values(1)
+            .long_help("Path the certificate is written to [default: ./ssl/localhost.crt]"),
+        Arg::with_name("key")
+            .long("key")
+            .takes_value(true)
+            .value_name("KEY_PATH")
+            .max_values(1)
+            .long_help("Path the private key is written to [default: ./ssl/localhost.key]"),
+        Arg::with_name("force")
+            .long("force")
+            .required(false)
+            .takes_value(false)
+            .long_help("Overwrites existing certificate and private key files"),
+    ]
+}
+
+pub struct Resolved {
+    pub config: ServerConfig,
+    pub errors: Vec<CliError>,
+    pub failed: Vec<&'static str>,
+}
 
-    let mut cli_config = CliConfig { cli_opts };
+pub fn parse_matches(matches: &clap::ArgMatches) -> Result<ServerConfig, CliError> {
+    let Resolved {
+        config, mut errors, ..
+    } = resolve(matches)?;
+    match errors.len() {
+        0 => {}
+        1 => return Err(errors.remove(0)),
+        _ => return Err(CliError::Multiple(errors)),
+    }
 
-    let https = cli_config.https();
-    cli_config.cli_opts.push(https_cert(matches, &https)?);
-    cli_config.cli_opts.push(https_priv_key(matches, &https)?);
+    let https = config.https();
+    if config.redirect_https && !https {
+        warn!("https redirect requested, but there is no https listener to redirect to");
+    }
+    if config.client_auth != ClientAuth::None && !https {
+        warn!("client authentication requested, but there is no https listener");
+    }
 
-    Ok(cli_config)
+    Ok(config)
 }
 
-fn address(matches: &clap::ArgMatches) -> Result<CliOpt, CliError> {
-    if let Some(v) = matches.value_of("address") {
-        match v.parse::<IpAddr>() {
-            Ok(v) => Ok(CliOpt::Address(v)),
-            Err(e) => {
-                error!("failed to parse the specified address: {}", e);
-                Err(CliError::Parse(e.to_string()))
+pub fn resolve(matches: &clap::ArgMatches) -> Result<Resolved, CliError> {
+    let file = config_file(matches)?;
+    let settings = Settings::new(matches, file.as_ref());
+    let mut errors = Vec::new();
+    let mut failed = Vec::new();
+    let mut config = ServerConfig::default();
+    set(&mut config.loglevel, loglevel(&settings), &mut errors);
+    set_path(
+        "directory",
+        &mut config.directory,
+        directory(&settings),
+        &mut errors,
+        &mut failed,
+    );
+    set(&mut config.threads, threads(&settings), &mut errors);
+    set(&mut config.reactors, reactors(&settings), &mut errors);
+    set(
+        &mut config.max_connections,
+        max_connections(&settings),
+        &mut errors,
+    );
+    set(
+        &mut config.timeouts.header,
+        timeout(&settings, "header-timeout", default::header_timeout()),
+        &mut errors,
+    );
+    set(
+        &mut config.timeouts.body,
+        timeout(&settings, "body-timeout", default::body_timeout()),
+        &mut errors,
+    );
+    set(
+        &mut config.timeouts.processing,
+        timeout(
+            &settings,
+            "processing-timeout",
+            default::processing_timeout(),
+        ),
+        &mut errors,
+    );
+    set(
+        &mut config.timeouts.write,
+        timeout(&settings, "write-timeout", default::write_timeout()),
+        &mut errors,
+    );
+    set(
+        &mut config.timeouts.idle,
+        timeout(&settings, "keepalive-timeout", default::idle_timeout()),
+        &mut errors,
+    );
+    set(&mut config.backlog, backlog(&settings), &mut errors);
+    set(&mut config.unix_mode, unix_mode(&settings), &mut errors);
+    set(&mut config.listen, listeners(&settings), &mut errors);
+    config.redirect_https = settings.is_present("redirect-https") || default::redirect_https();
+    config.ipv6_only = settings.is_present("ipv6-only") || default::ipv6_only();
+    config.reuse_address = !settings.is_present("no-reuse-address") && default::reuse_address();
+    config.nodelay = settings.is_present("nodelay") || default::nodelay();
+    #[cfg(unix)]
+    {
+        let inherited = systemd::listen_fds();
+        if !inherited.is_empty() {
+            info!(
+                "using {} inherited listener(s) instead of binding",
+                inherited.len()
+            );
+            config.listen = inherited;
+        }
+    }
+    if let Some(file) = &file {
+        if let Err(e) = file.apply(&mut config) {
+            errors.push(e);
+        }
+    }
+
+    let https = config.https();
+    config.https_self_signed =
+        settings.is_present("https-self-signed") || default::https_self_signed();
+    let files = https && !config.https_self_signed;
+    set_path(
+        "https-cert",
+        &mut config.https_cert,
+        https_cert(&settings, &files),
+        &mut errors,
+        &mut failed,
+    );
+    set_path(
+        "https-key",
+        &mut config.https_key,
+        https_priv_key(&settings, &files),
+        &mut errors,
+        &mut failed,
+    );
+    set_path(
+        "https-key-passphrase",
+        &mut config.https_key_passphrase,
+        key_passphrase(&settings),
+        &mut errors,
+        &mut failed,
+    );
+    set_path(
+        "https-ocsp",
+        &mut config.https_ocsp,
+        optional_path(&settings, "https-ocsp", &files),
+        &mut errors,
+        &mut failed,
+    );
+    set(
+        &mut config.self_signed_hosts,
+        self_signed_hosts(&settings),
+        &mut errors,
+    );
+    set(
+        &mut config.self_signed_days,
+        self_signed_days(&settings),
+        &mut errors,
+    );
+    set(
+        &mut config.cert_reload_interval,
+        timeout(
+            &settings,
+            "cert-reload-interval",
+            default::cert_reload_interval(),
+        ),
+        &mut errors,
+    );
+    set(
+        &mut config.tls_min_version,
+        tls_min_version(&settings),
+        &mut errors,
+    );
+    set(
+        &mut config.tls_ciphers,
+        tls_names(&settings, "tls-ciphers", |v| cipher_suite(v).map(|_| ())),
+        &mut errors,
+    );
+    set(
+        &mut config.tls_kx_groups,
+        tls_names(&settings, "tls-kx-groups", |v| kx_group(v).map(|_| ())),
+        &mut errors,
+    );
+    set(&mut config.alpn, alpn(&settings), &mut errors);
+    config.tls_tickets = settings.is_present("tls-tickets") || default::tls_tickets();
+    set(
+        &mut config.tls_ticket_rotation,
+        timeout(
+            &settings,
+            "tls-ticket-rotation",
+            default::tls_ticket_rotation(),
+        ),
+        &mut errors,
+    );
+    set(
+        &mut config.tls_session_cache,
+        tls_session_cache(&settings),
+        &mut errors,
+    );
+    set(&mut config.client_auth, client_auth(&settings), &mut errors);
+    set_path(
+        "client-ca",
+        &mut config.client_ca,
+        optional_path(&settings, "client-ca", &https),
+        &mut errors,
+        &mut failed,
+    );
+    set_path(
+        "client-crl",
+        &mut config.client_crl,
+        optional_path(&settings, "client-crl", &https),
+        &mut errors,
+        &mut failed,
+    );
+    if https
+        && config.client_auth != ClientAuth::None
+        && config.client_ca.is_none()
+        && !failed.contains(&"client-ca")
+    {
+        errors.push(CliError::Parse(format!(
+            "--client-auth {} requires --client-ca",
+            config.client_auth
+        )));
+    }
+
+    Ok(Resolved {
+        config,
+        errors,
+        failed,
+    })
+}
+
+fn set<T>(field: &mut T, result: Result<T, CliError>, errors: &mut Vec<CliError>) {
+    match result {
+        Ok(v) => *field = v,
+        Err(e) => errors.push(e),
+    }
+}
+
+fn set_path<T>(
+    name: &'static str,
+    field: &mut T,
+    result: Result<T, CliError>,
+    errors: &mut Vec<CliError>,
+    failed: &mut Vec<&'static str>,
+) {
+    if result.is_err() {
+        failed.push(name);
+    }
+    set(field, result, errors);
+}
+
+fn config_file(matches: &clap::ArgMatches) -> Result<Option<ConfigFile>, CliError> {
+    let path = match matches.value_of("config") {
+        Some(v) => PathBuf::from(v),
+        None => match std::env::var_os(env_name("config")) {
+            Some(v) => PathBuf::from(v),
+            None => return Ok(None),
+        },
+    };
+    let config = ConfigFile::load(&path)?;
+    info!("loaded configuration file: {}", path.display());
+    Ok(Some(config))
+}
+
+fn listeners(settings: &Settings) -> Result<Vec<Listener>, CliError> {
+    if let Some(values) = settings.values_of("listen") {
+        let mut listeners = Vec::new();
+        for v in values {
+            match v.parse::<Listener>() {
+                Ok(v) => listeners.push(v),
+                Err(e) => {
+                    error!("failed to parse the specified listener: {:?}", e);
+                    return Err(settings.error("listen", &v, e));
+                }
             }
         }
+        if !listeners.is_empty() {
+            return Ok(listeners);
+        }
+    }
+
+    let scheme = match https(settings) {
+        true => Scheme::Https,
+        false => Scheme::Http,
+    };
+    match (address(settings), port(settings)) {
+        (Ok(addresses), Ok(port)) => Ok(addresses
+            .into_iter()
+            .map(|address| Listener::new(scheme, SocketAddr::new(address, port)))
+            .collect()),
+        (Err(address), Err(port)) => Err(CliError::Multiple(vec![address, port])),
+        (Err(e), _) | (_, Err(e)) => Err(e),
+    }
+}
+
+fn address(settings: &Settings) -> Result<Vec<IpAddr>, CliError> {
+    if let Some(values) = settings.values_of("address") {
+        let mut addresses = Vec::new();
+        for v in values {
+            match v.parse::<IpAddr>() {
+                Ok(v) => addresses.push(v),
+                Err(e) => {
+                    error!("failed to parse the specified address: {}", e);
+                    return Err(settings.error("address", &v, e));
+                }
+            }
+        }
+        Ok(addresses)
     } else {
         warn!(
             "address not specified, using default: {:?}",
             default::address()
         );
-        Ok(CliOpt::Address(default::address()))
+        Ok(vec![default::address()])
     }
 }
 
-fn port(matches: &clap::ArgMatches) -> Result<CliOpt, CliError> {
-    if let Some(v) = matches.value_of("port") {
+fn backlog(settings: &Settings) -> Result<u32, CliError> {
+    if let Some(v) = settings.value_of("backlog") {
+        match v.parse::<u32>() {
+            Ok(0) => {
+                error!("listen backlog must be at least 1");
+                Err(settings.error("backlog", &v, "must be at least 1"))
+            }
+            Ok(v) => Ok(v),
+            Err(e) => {
+                error!("failed to parse listen backlog: {}", e);
+                Err(settings.error("backlog", &v, e))
+            }
+        }
+    } else {
+        Ok(default::backlog())
+    }
+}
+
+fn unix_mode(settings: &Settings) -> Result<Option<u32>, CliError> {
+    if let Some(v) = settings.value_of("unix-mode") {
+        match u32::from_str_radix(v.strip_prefix("0o").unwrap_or(&v), 8) {
+            Ok(mode) if mode <= 0o777 => Ok(Some(mode)),
+            Ok(mode) => {
+                error!("unix socket mode out of range: {:o}", mode);
+                Err(settings.error("unix-mode", &v, "must be at most 777"))
+            }
+            Err(e) => {
+                error!("failed to parse unix socket mode: {}", e);
+                Err(settings.error("unix-mode", &v, e))
+            }
+        }
+    } else {
+        Ok(None)
+    }
+}
+
+fn port(settings: &Settings) -> Result<u16, CliError> {
+    if let Some(v) = settings.value_of("port") {
         match v.parse::<u16>() {
-            Ok(v) => Ok(CliOpt::Port(v)),
+            Ok(v) => Ok(v),
             Err(e) => {
                 error!("failed to parse the specified port: {}", e);
-                Err(CliError::Parse(e.to_string()))
+                Err(settings.error("port", &v, e))
             }
         }
     } else {
         warn!("port not specified, using default: {}", default::port());
-        Ok(CliOpt::Port(default::port()))
+        Ok(default::port())
     }
 }
 
-fn directory(matches: &clap::ArgMatches) -> Result<CliOpt, CliError> {
-    if let Some(v) = matches.value_of("directory") {
-        match PathBuf::from(v).canonicalize() {
-            Ok(v) => Ok(CliOpt::Directory(v)),
+fn directory(settings: &Settings) -> Result<PathBuf, CliError> {
+    if let Some(v) = settings.value_of("directory") {
+        match PathBuf::from(&v).canonicalize() {
+            Ok(v) => Ok(v),
             Err(e) => {
                 error!("the specified path doesn't exist: {}", e);
-                Err(CliError::Parse(e.to_string()))
+                Err(settings.error("directory", &v, e))
             }
         }
     } else {
@@ -190,17 +759,17 @@ fn directory(matches: &clap::ArgMatches) -> Result<CliOpt, CliError> {
             "directory not specified, using default: {:?}",
             default::directory()
         );
-        Ok(CliOpt::Directory(default::directory()))
+        Ok(default::directory())
     }
 }
 
-fn loglevel(matches: &clap::ArgMatches) -> Result<CliOpt, CliError> {
-    if let Some(v) = matches.value_of("loglevel") {
+fn loglevel(settings: &Settings) -> Result<log::LevelFilter, CliError> {
+    if let Some(v) = settings.value_of("loglevel") {
         match log::LevelFilter::from_str(&v) {
-            Ok(v) => Ok(CliOpt::Verbosity(v)),
+            Ok(v) => Ok(v),
             Err(e) => {
                 error!("failed to parse log level: {}", e);
-                Err(CliError::Parse(e.to_string()))
+                Err(settings.error("loglevel", &v, e))
             }
         }
     } else {
@@ -208,13 +777,23 @@ fn loglevel(matches: &clap::ArgMatches) -> Result<CliOpt, CliError> {
             "log level not specified, using default: {}",
             default::loglevel()
         );
-        Ok(CliOpt::Verbosity(default::loglevel()))
+        Ok(default::loglevel())
     }
 }
 
-fn threads(matches: &clap::ArgMatches) -> Result<CliOpt, CliError> {
-    if let Some(v) = matches.value_of("threads") {
+fn threads(settings: &Settings) -> Result<usize, CliError> {
+    if let Some(v) = settings.value_of("threads") {
         match v.parse::<usize>() {
+            Ok(n)
+                if !(1..=default::threads_max()).contains(&n)
+                    && !matches!(settings.origin("threads"), Origin::Arg(_)) =>
+            {
+                Err(settings.error(
+                    "threads",
+                    &v,
+                    format!("must be between 1 and {}", default::threads_max()),
+                ))
+            }
             Ok(v) => {
                 if v > default::threads_max() {
                     warn!(
@@ -223,11 +802,11 @@ fn threads(matches: &clap::ArgMatches) -> Result<CliOpt, CliError> {
                         v
                     );
                 }
-                Ok(CliOpt::Threads(std::cmp::min(v, default::threads_max())))
+                Ok(std::cmp::min(v, default::threads_max()))
             }
             Err(e) => {
                 error!("failed to parse thread count: {}", e);
-                Err(CliError::Parse(e.to_string()))
+                Err(settings.error("threads", &v, e))
             }
         }
     } else {
@@ -235,30 +814,86 @@ fn threads(matches: &clap::ArgMatches) -> Result<CliOpt, CliError> {
             "thread count not specified, using default: {}",
             default::threads()
         );
-        Ok(CliOpt::Threads(default::threads()))
+        Ok(default::threads())
+    }
+}
+
+fn max_connections(settings: &Settings) -> Result<usize, CliError> {
+    if let Some(v) = settings.value_of("max-connections") {
+        match v.parse::<usize>() {
+            Ok(0) => {
+                error!("max connection count must be at least 1");
+                Err(settings.error("max-connections", &v, "must be at least 1"))
+            }
+            Ok(v) => Ok(v),
+            Err(e) => {
+                error!("failed to parse max connection count: {}", e);
+                Err(settings.error("max-connections", &v, e))
+            }
+        }
+    } else {
+        warn!(
+            "max connection count not specified, using default: {}",
+            default::max_connections()
+        );
+        Ok(default::max_connections())
+    }
+}
+
+fn timeout(settings: &Settings, name: &str, default: Duration) -> Result<Duration, CliError> {
+    if let Some(v) = settings.value_of(name) {
+        match v.parse::<u64>() {
+            Ok(0) => {
+                error!("{} must be at least 1 second", name);
+                Err(settings.error(name, &v, "must be at least 1 second"))
+            }
+            Ok(v) => Ok(Duration::from_secs(v)),
+            Err(e) => {
+                error!("failed to parse {}: {}", name, e);
+                Err(settings.error(name, &v, e))
+            }
+        }
+    } else {
+        Ok(default)
+    }
+}
+
+fn reactors(settings: &Settings) -> Result<bool, CliError> {
+    if settings.is_present("reactors") {
+        if cfg!(all(
+            unix,
+            not(any(target_os = "solaris", target_os = "illumos"))
+        )) {
+            Ok(true)
+        } else {
+            warn!("reactor mode requires SO_REUSEPORT, which this platform doesn't support");
+            Ok(false)
+        }
+    } else {
+        Ok(default::reactors())
     }
 }
 
-fn https(matches: &clap::ArgMatches) -> Result<CliOpt, CliError> {
-    if matches.is_present("https") {
-        Ok(CliOpt::Https(true))
+fn https(settings: &Settings) -> bool {
+    if settings.is_present("https") || settings.is_present("https-self-signed") {
+        true
     } else {
         warn!(
             "https option not specified, using default: {}",
             default::https()
         );
-        Ok(CliOpt::Https(default::https()))
+        default::https()
     }
 }
 
-fn https_cert(matches: &clap::ArgMatches, https: &bool) -> Result<CliOpt, CliError> {
+fn https_cert(settings: &Settings, https: &bool) -> Result<PathBuf, CliError> {
     if *https {
-        if let Some(v) = matches.value_of("https-cert") {
-            match PathBuf::from(v).canonicalize() {
-                Ok(v) => Ok(CliOpt::HttpsCert(Some(v))),
+        if let Some(v) = settings.value_of("https-cert") {
+            match PathBuf::from(&v).canonicalize() {
+                Ok(v) => Ok(v),
                 Err(e) => {
                     error!("the specified certificate path doesn't exist: {}", e);
-                    Err(CliError::Parse(e.to_string()))
+                    Err(settings.error("https-cert", &v, e))
                 }
             }
         } else {
@@ -266,21 +901,21 @@ fn https_cert(matches: &clap::ArgMatches, https: &bool) -> Result<CliOpt, CliErr
                 "certificate path not specified, using default: {:?}",
                 default::https_cert()
             );
-            Ok(CliOpt::HttpsCert(Some(default::https_cert())))
+            Ok(default::https_cert())
         }
     } else {
-        Ok(CliOpt::HttpsCert(None))
+        Ok(default::https_cert())
     }
 }
 
-fn https_priv_key(matches: &clap::ArgMatches, https: &bool) -> Result<CliOpt, CliError> {
+fn https_priv_key(settings: &Settings, https: &bool) -> Result<PathBuf, CliError> {
     if *https {
-        if let Some(v) = matches.value_of("https-key") {
-            match PathBuf::from(v).canonicalize() {
-                Ok(v) => Ok(CliOpt::HttpsPrivKey(Some(v))),
+        if let Some(v) = settings.value_of("https-key") {
+            match PathBuf::from(&v).canonicalize() {
+                Ok(v) => Ok(v),
                 Err(e) => {
                     error!("the specified private key path doesn't exist: {}", e);
-                    Err(CliError::Parse(e.to_string()))
+                    Err(settings.error("https-key", &v, e))
                 }
             }
         } else {
@@ -288,9 +923,190 @@ fn https_priv_key(matches: &clap::ArgMatches, https: &bool) -> Result<CliOpt, Cl
                 "private key path not specified, using default: {:?}",
                 default::https_priv_key()
             );
-            Ok(CliOpt::HttpsPrivKey(Some(default::https_priv_key())))
+            Ok(default::https_priv_key())
+        }
+    } else {
+        Ok(default::https_priv_key())
+    }
+}
+
+fn self_signed_hosts(settings: &Settings) -> Result<Vec<String>, CliError> {
+    match settings.values_of("self-signed-hosts") {
+        Some(v) if v.is_empty() => {
+            Err(settings.error("self-signed-hosts", "", "expected at least one host name"))
+        }
+        Some(v) => Ok(v),
+        None => Ok(default::self_signed_hosts()),
+    }
+}
+
+fn self_signed_days(settings: &Settings) -> Result<u32, CliError> {
+    if let Some(v) = settings.value_of("self-signed-days") {
+        match v.parse::<u32>() {
+            Ok(days) if days == 0 || days > default::self_signed_days_max() => Err(settings.error(
+                "self-signed-days",
+                &v,
+                format!("must be between 1 and {}", default::self_signed_days_max()),
+            )),
+            Ok(v) => Ok(v),
+            Err(e) => {
+                error!("failed to parse certificate validity: {}", e);
+                Err(settings.error("self-signed-days", &v, e))
+            }
         }
     } else {
-        Ok(CliOpt::HttpsPrivKey(None))
+        Ok(default::self_signed_days())
+    }
+}
+
+fn client_auth(settings: &Settings) -> Result<ClientAuth, CliError> {
+    if let Some(v) = settings.value_of("client-auth") {
+        match ClientAuth::from_str(&v) {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                error!("failed to parse client auth mode: {}", e);
+                Err(settings.error("client-auth", &v, e))
+            }
+        }
+    } else {
+        Ok(default::client_auth())
+    }
+}
+
+fn key_passphrase(settings: &Settings) -> Result<Option<Passphrase>, CliError> {
+    match settings.value_of("https-key-passphrase") {
+        Some(v) => match Passphrase::from_str(&v) {
+            Ok(v) => Ok(Some(v)),
+            Err(e) => {
+                error!("failed to parse key passphrase source: {}", e);
+                Err(settings.error("https-key-passphrase", &v, e))
+            }
+        },
+        None => Ok(None),
+    }
+}
+
+fn optional_path(
+    settings: &Settings,
+    name: &str,
+    https: &bool,
+) -> Result<Option<PathBuf>, CliError> {
+    match settings.value_of(name) {
+        Some(v) if *https => match PathBuf::from(&v).canonicalize() {
+            Ok(v) => Ok(Some(v)),
+            Err(e) => {
+                error!("the specified {} path doesn't exist: {}", name, e);
+                Err(settings.error(name, &v, e))
+            }
+        },
+        Some(v) => Ok(Some(PathBuf::from(v))),
+        None => Ok(None),
+    }
+}
+
+fn tls_min_version(settings: &Settings) -> Result<TlsVersion, CliError> {
+    if let Some(v) = settings.value_of("tls-min-version") {
+        match TlsVersion::from_str(&v) {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                error!("failed to parse tls version: {}", e);
+                Err(settings.error("tls-min-version", &v, e))
+            }
+        }
+    } else {
+        Ok(default::tls_min_version())
+    }
+}
+
+fn tls_names(
+    settings: &Settings,
+    name: &str,
+    check: impl Fn(&str) -> Result<(), String>,
+) -> Result<Vec<String>, CliError> {
+    let values = settings.values_of(name).unwrap_or_default();
+    for v in &values {
+        if let Err(e) = check(v) {
+            error!("failed to parse {}: {}", name, e);
+            return Err(settings.error(name, v, e));
+        }
+    }
+    Ok(values)
+}
+
+fn alpn(settings: &Settings) -> Result<Vec<String>, CliError> {
+    if let Some(values) = settings.values_of("alpn") {
+        let mut protocols = Vec::new();
+        for v in values {
+            match alpn_protocol(&v) {
+                Ok(v) => protocols.push(v),
+                Err(e) => {
+                    error!("failed to parse alpn protocol: {}", e);
+                    return Err(settings.error("alpn", &v, e));
+                }
+            }
+        }
+        Ok(protocols)
+    } else {
+        Ok(default::alpn())
+    }
+}
+
+fn tls_session_cache(settings: &Settings) -> Result<usize, CliError> {
+    if let Some(v) = settings.value_of("tls-session-cache") {
+        match v.parse::<usize>() {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                error!("failed to parse tls session cache size: {}", e);
+                Err(settings.error("tls-session-cache", &v, e))
+            }
+        }
+    } else {
+        Ok(default::tls_session_cache())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve_toml(name: &str, toml: &str) -> Resolved {
+        let path = std::env::temp_dir().join(format!("twebd-{}-{}", std::process::id(), name));
+        std::fs::write(&path, toml).unwrap();
+        let matches = App::new(APP_NAME).args(&args()).get_matches_from(vec![
+            "twebd",
+            "--config",
+            path.to_str().unwrap(),
+        ]);
+        let resolved = resolve(&matches).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        resolved
+    }
+
+    #[test]
+    fn unix_mode_from_toml() {
+        for (name, toml) in [
+            ("octal.toml", "unix-mode = 0o660\n"),
+            ("string.toml", "unix-mode = \"660\"\n"),
+            ("prefixed.toml", "unix-mode = \"0o660\"\n"),
+        ] {
+            let resolved = resolve_toml(name, toml);
+            assert!(
+                resolved.errors.is_empty(),
+                "{}: {:?}",
+                name,
+                resolved.errors
+            );
+            assert_eq!(resolved.config.unix_mode, Some(0o660), "{}", name);
+        }
+        let resolved = resolve_toml("decimal.toml", "unix-mode = 660\n");
+        assert_eq!(resolved.failed, Vec::<&str>::new());
+        match resolved.errors.as_slice() {
+            [CliError::Config(e)] => assert!(
+                e.ends_with("unix-mode 0o1224: must be at most 777"),
+                "{}",
+                e
+            ),
+            v => panic!("unexpected errors: {:?}", v),
+        }
     }
 }
@@ -1,24 +1,120 @@
+#[cfg(unix)]
+use crate::net::systemd;
 use crate::{
-    cli::{default, parse},
+    cli::{check, default, gen_cert, parse},
+    net::Scheme,
     srv::{
-        log::init_logger, ConnectionHandler, HttpServer, HttpsServer, SecureConnectionHandler,
-        Server,
+        log::init_logger, reactor, Connection, ConnectionHandler, HttpServer, HttpsServer, Server,
     },
 };
-use log::error;
+use log::{debug, error, info, warn};
+use std::{process::ExitCode, sync::Arc};
 
-pub fn run() {
+pub fn run() -> ExitCode {
     let _ = init_logger(default::loglevel());
     let matches = parse::parse_args();
-    match parse::parse_matches(&matches) {
-        Ok(cli_config) => {
-            log::set_max_level(cli_config.log_level());
-            if cli_config.https() {
-                HttpsServer::new(cli_config.cli_opts()).listen();
-            } else {
-                HttpServer::new(cli_config.cli_opts()).listen();
+    if let ("check", Some(v)) = matches.subcommand() {
+        return check::check(v);
+    }
+    if let ("gen-cert", Some(v)) = matches.subcommand() {
+        return gen_cert::gen_cert(v);
+    }
+    if matches.is_present("print-config") {
+        return print_config(&matches);
+    }
+    let config = match parse::parse_matches(&matches) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+    if let Some(v) = &config.https_key_passphrase {
+        v.take_env();
+    }
+    log::set_max_level(config.loglevel);
+    let mut reactors: Vec<reactor::Reactor> = Vec::new();
+    for listener in &config.listen {
+        let name = listener.to_string();
+        let (config, listener) = (config.clone(), listener.clone());
+        let n = match config.reactors_for(&listener) {
+            true => config.threads,
+            false => 1,
+        };
+        let reactor: reactor::Reactor = match listener.scheme {
+            Scheme::Http => {
+                Arc::new(
+                    move |ready| match HttpServer::<Connection>::new(&config, &listener) {
+                        Ok(mut server) => {
+                            ready();
+                            server.listen()
+                        }
+                        Err(e) => error!("unable to start listener {}: {}", name, e),
+                    },
+                )
             }
+            Scheme::Https => Arc::new(move |ready| match HttpsServer::new(&config, &listener) {
+                Ok(mut server) => {
+                    ready();
+                    server.listen()
+                }
+                Err(e) => error!("unable to start listener {}: {}", name, e),
+            }),
+        };
+        for _ in 0..n {
+            reactors.push(Arc::clone(&reactor));
         }
-        Err(e) => error!("{:?}", e),
+    }
+    #[cfg(unix)]
+    reactor::handle_signals();
+    let ok = reactor::run(reactors, ready, || notify("STOPPING=1"));
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn print_config(matches: &clap::ArgMatches) -> ExitCode {
+    log::set_max_level(log::LevelFilter::Off);
+    match parse::parse_matches(matches).and_then(|v| v.to_toml()) {
+        Ok(v) => {
+            print!("{}", v);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn ready() {
+    notify("READY=1");
+    #[cfg(unix)]
+    if let Some(interval) = systemd::watchdog() {
+        info!("sending watchdog keep-alive every {:?}", interval);
+        let _ = std::thread::Builder::new()
+            .name(String::from("watchdog"))
+            .spawn(move || loop {
+                std::thread::sleep(interval);
+                if reactor::is_stopping() {
+                    break;
+                }
+                if reactor::is_alive(interval + reactor::HEARTBEAT) {
+                    notify("WATCHDOG=1");
+                } else {
+                    warn!("a reactor stopped responding, withholding the watchdog keep-alive");
+                }
+            });
+    }
+}
+
+fn notify(state: &str) {
+    #[cfg(unix)]
+    match systemd::notify(state) {
+        Ok(true) => debug!("notified service manager: {}", state),
+        Ok(false) => {}
+        Err(e) => warn!("error notifying service manager: {:?}", e),
     }
 }
@@ -1,8 +1,8 @@
+extern crate base64;
 extern crate log;
 extern crate mio;
 extern crate regex;
 extern crate rustls;
-extern crate rustls_pemfile;
 
 pub mod cli;
 pub mod net;
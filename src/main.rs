@@ -1,6 +1,6 @@
 // TODO: Support implementing routes for handling different types of requests.
 // TODO: Implement multi-threading with mio.
 
-fn main() {
-    twebd::run();
+fn main() -> std::process::ExitCode {
+    twebd::run()
 }
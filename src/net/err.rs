@@ -1,15 +1,35 @@
-use std::io;
+use std::{fmt, io};
 
 #[derive(Debug)]
 pub enum SocketError {
     InUse,
     NotConnected,
     InsufficientPrivileges,
+    Address(String),
     Io(io::Error),
 }
 
 impl From<io::Error> for SocketError {
     fn from(e: io::Error) -> Self {
-        Self::Io(e)
+        match e.kind() {
+            io::ErrorKind::AddrInUse => Self::InUse,
+            io::ErrorKind::PermissionDenied => Self::InsufficientPrivileges,
+            _ => Self::Io(e),
+        }
+    }
+}
+
+impl fmt::Display for SocketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InUse => write!(f, "address already in use, is another server running?"),
+            Self::NotConnected => write!(f, "socket is not connected"),
+            Self::InsufficientPrivileges => write!(
+                f,
+                "permission denied, ports below 1024 and some socket paths need elevated privileges"
+            ),
+            Self::Address(v) => write!(f, "invalid address: {}", v),
+            Self::Io(e) => write!(f, "{}", e),
+        }
     }
 }
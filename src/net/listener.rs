@@ -0,0 +1,137 @@
+#[cfg(unix)]
+use crate::net::systemd;
+use crate::net::SocketError;
+#[cfg(unix)]
+use std::os::unix::io::RawFd;
+use std::{
+    fmt,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Scheme {
+    Http,
+    Https,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListenAddr {
+    Inet(SocketAddr),
+    Unix(PathBuf),
+    #[cfg(unix)]
+    Fd(RawFd),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Listener {
+    pub scheme: Scheme,
+    pub address: ListenAddr,
+}
+
+impl Listener {
+    pub fn new(scheme: Scheme, address: SocketAddr) -> Listener {
+        Listener {
+            scheme,
+            address: ListenAddr::Inet(address),
+        }
+    }
+    pub fn unix(scheme: Scheme, path: &Path) -> Listener {
+        Listener {
+            scheme,
+            address: ListenAddr::Unix(path.to_path_buf()),
+        }
+    }
+    #[cfg(unix)]
+    pub fn fd(scheme: Scheme, fd: RawFd) -> Listener {
+        Listener {
+            scheme,
+            address: ListenAddr::Fd(fd),
+        }
+    }
+    pub fn port(&self) -> Option<u16> {
+        match &self.address {
+            ListenAddr::Inet(v) => Some(v.port()),
+            ListenAddr::Unix(_) => None,
+            #[cfg(unix)]
+            ListenAddr::Fd(v) => systemd::local_addr(*v).map(|v| v.port()),
+        }
+    }
+}
+
+impl FromStr for Listener {
+    type Err = SocketError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(address) = s.parse::<SocketAddr>() {
+            return Ok(Listener::new(Scheme::Http, address));
+        }
+        let (scheme, address) = match s.split_once(':') {
+            Some(v) => v,
+            None => return Err(SocketError::Address(s.to_owned())),
+        };
+        let scheme = match scheme.to_ascii_lowercase().as_str() {
+            "unix" if !address.is_empty() => {
+                return Ok(Listener::unix(Scheme::Http, Path::new(address)))
+            }
+            "http" => Scheme::Http,
+            "https" => Scheme::Https,
+            #[cfg(unix)]
+            "http+fd" => return fd(Scheme::Http, address, s),
+            #[cfg(unix)]
+            "https+fd" => return fd(Scheme::Https, address, s),
+            _ => return Err(SocketError::Address(s.to_owned())),
+        };
+        match address.strip_prefix("//").map(|v| v.parse::<SocketAddr>()) {
+            Some(Ok(address)) => Ok(Listener::new(scheme, address)),
+            _ => Err(SocketError::Address(s.to_owned())),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn fd(scheme: Scheme, address: &str, s: &str) -> Result<Listener, SocketError> {
+    match address.parse::<RawFd>() {
+        Ok(fd) if fd >= 0 => Ok(Listener::fd(scheme, fd)),
+        _ => Err(SocketError::Address(s.to_owned())),
+    }
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scheme = match self.scheme {
+            Scheme::Http => "http",
+            Scheme::Https => "https",
+        };
+        match &self.address {
+            ListenAddr::Inet(v) => write!(f, "{}://{}", scheme, v),
+            ListenAddr::Unix(v) => write!(f, "unix:{}", v.display()),
+            #[cfg(unix)]
+            ListenAddr::Fd(v) => write!(f, "{}+fd:{}", scheme, v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_round_trips() {
+        let mut listeners = vec![
+            Listener::new(Scheme::Http, "0.0.0.0:80".parse().unwrap()),
+            Listener::new(Scheme::Https, "[::1]:443".parse().unwrap()),
+            Listener::unix(Scheme::Http, Path::new("/run/twebd.sock")),
+        ];
+        #[cfg(unix)]
+        listeners.extend([
+            Listener::fd(Scheme::Http, 3),
+            Listener::fd(Scheme::Https, 4),
+        ]);
+        for listener in listeners {
+            assert_eq!(listener.to_string().parse::<Listener>().unwrap(), listener);
+        }
+        assert!("https+fd:-1".parse::<Listener>().is_err());
+    }
+}
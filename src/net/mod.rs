@@ -1,12 +1,22 @@
 pub mod err;
+pub mod listener;
 pub mod socket;
+pub mod stream;
+#[cfg(unix)]
+pub mod systemd;
 pub mod tcp;
 pub mod udp;
+#[cfg(unix)]
+pub mod unix;
 
 pub use err::SocketError;
-pub use socket::{Socket, SocketBuilder};
-pub use tcp::{SimpleTcpSocket, TcpSocket};
+pub use listener::{ListenAddr, Listener, Scheme};
+pub use socket::{ListenSocket, Socket, SocketBuilder};
+pub use stream::{Peer, Stream};
+pub use tcp::{SimpleTcpSocket, TcpOptions, TcpSocket};
 pub use udp::{UdpSocket, UdpSocketIo};
+#[cfg(unix)]
+pub use unix::UnixSocket;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum DataProtocol {
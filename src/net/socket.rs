@@ -1,11 +1,18 @@
+#[cfg(unix)]
+use crate::net::UnixSocket;
 use crate::{
-    cli::{Builder, CliOpt},
-    net::{SimpleTcpSocket, SocketError, TcpSocket, UdpSocket},
+    cli::{default, Builder, ServerConfig},
+    net::{
+        ListenAddr, Listener, Peer, SimpleTcpSocket, SocketError, Stream, TcpOptions, TcpSocket,
+        UdpSocket,
+    },
 };
 use log::trace;
+use std::{fmt, io, marker::PhantomData, net::IpAddr, path::PathBuf};
+#[cfg(unix)]
 use std::{
-    marker::PhantomData,
-    net::{IpAddr, Ipv4Addr},
+    mem::ManuallyDrop,
+    os::unix::io::{FromRawFd, RawFd},
 };
 
 #[derive(Debug)]
@@ -15,83 +22,186 @@ pub enum Socket {
 }
 
 #[derive(Debug)]
-pub struct SocketBuilder<T> {
-    address: IpAddr,
-    port: u16,
-    _other: Vec<CliOpt>,
-    socket_type: PhantomData<T>,
+pub enum ListenSocket {
+    Tcp(TcpSocket),
+    #[cfg(unix)]
+    Unix(UnixSocket),
 }
 
-impl Builder<Self, TcpSocket, SocketError> for SocketBuilder<TcpSocket> {
-    fn new(opts: Vec<CliOpt>) -> Self {
-        let mut socket_builder = Self::default();
-        for opt in opts {
-            match opt {
-                CliOpt::Address(v) => socket_builder.address = v,
-                CliOpt::Port(v) => socket_builder.port = v,
-                cli_opt => socket_builder.add_other(cli_opt.to_owned()),
-            }
+impl ListenSocket {
+    #[cfg(unix)]
+    pub fn from_fd(fd: RawFd, nodelay: bool) -> io::Result<ListenSocket> {
+        let listener = ManuallyDrop::new(unsafe { std::net::TcpListener::from_raw_fd(fd) });
+        if listener.local_addr().is_ok() {
+            let socket = listener.try_clone()?;
+            socket.set_nonblocking(true)?;
+            return Ok(ListenSocket::Tcp(TcpSocket::from_std(socket, nodelay)));
         }
+        let listener =
+            ManuallyDrop::new(unsafe { std::os::unix::net::UnixListener::from_raw_fd(fd) });
+        Ok(ListenSocket::Unix(UnixSocket::from_std(
+            listener.try_clone()?,
+        )?))
+    }
+    pub fn accept(&self) -> io::Result<(Stream, Peer)> {
+        match self {
+            ListenSocket::Tcp(v) => v
+                .accept()
+                .map(|(stream, address)| (Stream::Tcp(stream), Peer::Tcp(address))),
+            #[cfg(unix)]
+            ListenSocket::Unix(v) => v
+                .accept()
+                .map(|(stream, address)| (Stream::Unix(stream), Peer::Unix(address))),
+        }
+    }
+}
 
-        trace!("constructed socket builder: {:?}", &socket_builder);
-
-        socket_builder
+impl fmt::Display for ListenSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenSocket::Tcp(v) => match v.socket().local_addr() {
+                Ok(address) => write!(f, "{}", address),
+                Err(_) => write!(f, "(unknown)"),
+            },
+            #[cfg(unix)]
+            ListenSocket::Unix(v) => write!(f, "unix:{}", v.path().display()),
+        }
     }
-    fn build(&self) -> Result<TcpSocket, SocketError> {
-        Ok(TcpSocket::new(self.address, self.port))
+}
+
+impl mio::event::Source for ListenSocket {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        match self {
+            ListenSocket::Tcp(v) => v.register(registry, token, interests),
+            #[cfg(unix)]
+            ListenSocket::Unix(v) => v.register(registry, token, interests),
+        }
     }
-    fn add_other(&mut self, o: CliOpt) {
-        self._other.push(o);
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        match self {
+            ListenSocket::Tcp(v) => v.reregister(registry, token, interests),
+            #[cfg(unix)]
+            ListenSocket::Unix(v) => v.reregister(registry, token, interests),
+        }
     }
-    fn other(&self) -> Vec<CliOpt> {
-        self._other.to_vec()
+    fn deregister(&mut self, registry: &mio::Registry) -> io::Result<()> {
+        match self {
+            ListenSocket::Tcp(v) => v.deregister(registry),
+            #[cfg(unix)]
+            ListenSocket::Unix(v) => v.deregister(registry),
+        }
     }
 }
 
-impl Builder<Self, SimpleTcpSocket, SocketError> for SocketBuilder<SimpleTcpSocket> {
-    fn new(opts: Vec<CliOpt>) -> Self {
-        let mut socket_builder = Self::default();
-        for opt in opts {
-            match opt {
-                CliOpt::Address(v) => socket_builder.address = v,
-                CliOpt::Port(v) => socket_builder.port = v,
-                cli_opt => socket_builder.add_other(cli_opt.to_owned()),
+#[derive(Debug)]
+pub struct SocketBuilder<T> {
+    address: IpAddr,
+    port: u16,
+    tcp_options: TcpOptions,
+    unix_path: Option<PathBuf>,
+    unix_mode: Option<u32>,
+    listen_fd: Option<i32>,
+    socket_type: PhantomData<T>,
+}
+
+impl<T: fmt::Debug> SocketBuilder<T> {
+    fn from_config(config: &ServerConfig, listener: &Listener) -> Self {
+        let mut socket_builder = SocketBuilder::<T> {
+            address: default::address(),
+            port: default::port(),
+            tcp_options: TcpOptions {
+                backlog: config.backlog,
+                reuse_address: config.reuse_address,
+                reuse_port: config.reactors_for(listener),
+                ipv6_only: config.ipv6_only,
+                nodelay: config.nodelay,
+            },
+            unix_path: None,
+            unix_mode: config.unix_mode,
+            listen_fd: None,
+            socket_type: PhantomData,
+        };
+        match &listener.address {
+            ListenAddr::Inet(v) => {
+                socket_builder.address = v.ip();
+                socket_builder.port = v.port();
             }
+            ListenAddr::Unix(v) => socket_builder.unix_path = Some(v.to_path_buf()),
+            #[cfg(unix)]
+            ListenAddr::Fd(v) => socket_builder.listen_fd = Some(*v),
         }
 
         trace!("constructed socket builder: {:?}", &socket_builder);
 
         socket_builder
     }
-    fn build(&self) -> Result<SimpleTcpSocket, SocketError> {
-        Ok(SimpleTcpSocket::new(self.address, self.port))
-    }
-    fn add_other(&mut self, o: CliOpt) {
-        self._other.push(o);
+}
+
+impl Builder<Self, TcpSocket, SocketError> for SocketBuilder<TcpSocket> {
+    fn new(config: &ServerConfig, listener: &Listener) -> Self {
+        Self::from_config(config, listener)
     }
-    fn other(&self) -> Vec<CliOpt> {
-        self._other.to_vec()
+    fn build(&self) -> Result<TcpSocket, SocketError> {
+        TcpSocket::new(self.address, self.port, &self.tcp_options)
     }
 }
 
-impl Default for SocketBuilder<TcpSocket> {
-    fn default() -> Self {
-        SocketBuilder::<TcpSocket> {
-            address: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
-            port: 8080,
-            _other: Vec::new(),
-            socket_type: PhantomData,
+impl Builder<Self, ListenSocket, SocketError> for SocketBuilder<ListenSocket> {
+    fn new(config: &ServerConfig, listener: &Listener) -> Self {
+        Self::from_config(config, listener)
+    }
+    fn build(&self) -> Result<ListenSocket, SocketError> {
+        #[cfg(unix)]
+        if let Some(fd) = self.listen_fd {
+            return Ok(ListenSocket::from_fd(fd, self.tcp_options.nodelay)?);
+        }
+        #[cfg(unix)]
+        if let Some(path) = &self.unix_path {
+            return Ok(ListenSocket::Unix(UnixSocket::new(path, self.unix_mode)?));
+        }
+        Ok(ListenSocket::Tcp(TcpSocket::new(
+            self.address,
+            self.port,
+            &self.tcp_options,
+        )?))
+    }
+    fn check(&self) -> Result<(), SocketError> {
+        #[cfg(unix)]
+        if self.listen_fd.is_some() {
+            return Ok(());
+        }
+        #[cfg(unix)]
+        if let Some(path) = &self.unix_path {
+            return match path.parent() {
+                Some(v) if v.as_os_str().is_empty() || v.is_dir() => Ok(()),
+                _ => Err(SocketError::Address(format!(
+                    "{}: parent directory doesn't exist",
+                    path.display()
+                ))),
+            };
         }
+        if self.port == 0 {
+            return Err(SocketError::Address(format!("{}:0", self.address)));
+        }
+        Ok(())
     }
 }
 
-impl Default for SocketBuilder<SimpleTcpSocket> {
-    fn default() -> Self {
-        SocketBuilder::<SimpleTcpSocket> {
-            address: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
-            port: 8080,
-            _other: Vec::new(),
-            socket_type: PhantomData,
-        }
+impl Builder<Self, SimpleTcpSocket, SocketError> for SocketBuilder<SimpleTcpSocket> {
+    fn new(config: &ServerConfig, listener: &Listener) -> Self {
+        Self::from_config(config, listener)
+    }
+    fn build(&self) -> Result<SimpleTcpSocket, SocketError> {
+        SimpleTcpSocket::new(self.address, self.port)
     }
 }
@@ -0,0 +1,103 @@
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    net::{Shutdown, SocketAddr},
+};
+
+#[derive(Debug)]
+pub enum Stream {
+    Tcp(mio::net::TcpStream),
+    #[cfg(unix)]
+    Unix(mio::net::UnixStream),
+}
+
+impl Stream {
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        match self {
+            Stream::Tcp(v) => v.shutdown(how),
+            #[cfg(unix)]
+            Stream::Unix(v) => v.shutdown(how),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(v) => v.read(buf),
+            #[cfg(unix)]
+            Stream::Unix(v) => v.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(v) => v.write(buf),
+            #[cfg(unix)]
+            Stream::Unix(v) => v.write(buf),
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(v) => v.flush(),
+            #[cfg(unix)]
+            Stream::Unix(v) => v.flush(),
+        }
+    }
+}
+
+impl mio::event::Source for Stream {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        match self {
+            Stream::Tcp(v) => v.register(registry, token, interests),
+            #[cfg(unix)]
+            Stream::Unix(v) => v.register(registry, token, interests),
+        }
+    }
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        match self {
+            Stream::Tcp(v) => v.reregister(registry, token, interests),
+            #[cfg(unix)]
+            Stream::Unix(v) => v.reregister(registry, token, interests),
+        }
+    }
+    fn deregister(&mut self, registry: &mio::Registry) -> io::Result<()> {
+        match self {
+            Stream::Tcp(v) => v.deregister(registry),
+            #[cfg(unix)]
+            Stream::Unix(v) => v.deregister(registry),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Peer {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(mio::net::SocketAddr),
+}
+
+impl fmt::Display for Peer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Peer::Tcp(v) => write!(f, "{}", v),
+            #[cfg(unix)]
+            Peer::Unix(v) => match v.as_pathname() {
+                Some(path) => write!(f, "unix:{}", path.display()),
+                None => write!(f, "unix:(unnamed)"),
+            },
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::net::{Listener, Scheme};
+use log::{debug, trace, warn};
+use std::{
+    env, io,
+    mem::ManuallyDrop,
+    net::SocketAddr,
+    os::unix::{io::FromRawFd, io::RawFd, net::UnixDatagram},
+    time::Duration,
+};
+
+pub const LISTEN_FDS_START: RawFd = 3;
+
+pub fn listen_fds() -> Vec<Listener> {
+    let pid = match env::var("LISTEN_PID").map(|v| v.parse::<u32>()) {
+        Ok(Ok(v)) => v,
+        _ => return Vec::new(),
+    };
+    if pid != std::process::id() {
+        debug!("LISTEN_PID is {}, not for this process", pid);
+        return Vec::new();
+    }
+    let n = match env::var("LISTEN_FDS").map(|v| v.parse::<RawFd>()) {
+        Ok(Ok(v)) => v,
+        _ => {
+            warn!("LISTEN_PID is set, but LISTEN_FDS is missing or invalid");
+            return Vec::new();
+        }
+    };
+    let names: Vec<String> = match env::var("LISTEN_FDNAMES") {
+        Ok(v) => v.split(':').map(|v| v.to_owned()).collect(),
+        Err(_) => Vec::new(),
+    };
+    env::remove_var("LISTEN_PID");
+    env::remove_var("LISTEN_FDS");
+    env::remove_var("LISTEN_FDNAMES");
+
+    (0..n)
+        .map(|i| {
+            let fd = LISTEN_FDS_START + i;
+            let scheme = match names.get(i as usize).map(|v| v.as_str()) {
+                Some("https") => Scheme::Https,
+                _ => Scheme::Http,
+            };
+            trace!("inherited listener fd {} as {:?}", fd, scheme);
+            Listener::fd(scheme, fd)
+        })
+        .collect()
+}
+
+pub fn local_addr(fd: RawFd) -> Option<SocketAddr> {
+    let listener = ManuallyDrop::new(unsafe { std::net::TcpListener::from_raw_fd(fd) });
+    listener.local_addr().ok()
+}
+
+pub fn notify(state: &str) -> io::Result<bool> {
+    let path = match env::var_os("NOTIFY_SOCKET") {
+        Some(v) => v,
+        None => return Ok(false),
+    };
+    let socket = UnixDatagram::unbound()?;
+    let path = path.to_string_lossy();
+    trace!("sending {:?} to notify socket {}", state, &path);
+    match path.strip_prefix('@') {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        Some(name) => {
+            #[cfg(target_os = "android")]
+            use std::os::android::net::SocketAddrExt;
+            #[cfg(target_os = "linux")]
+            use std::os::linux::net::SocketAddrExt;
+            let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(state.as_bytes(), &address)?;
+        }
+        _ => {
+            socket.send_to(state.as_bytes(), path.as_ref())?;
+        }
+    }
+    Ok(true)
+}
+
+pub fn watchdog() -> Option<Duration> {
+    if let Ok(Ok(pid)) = env::var("WATCHDOG_PID").map(|v| v.parse::<u32>()) {
+        if pid != std::process::id() {
+            return None;
+        }
+    }
+    match env::var("WATCHDOG_USEC").map(|v| v.parse::<u64>()) {
+        Ok(Ok(v)) if v > 0 => Some(Duration::from_micros(v / 2)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notify_socket() {
+        let path = env::temp_dir().join(format!("twebd-{}-notify.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let socket = UnixDatagram::bind(&path).unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        env::remove_var("NOTIFY_SOCKET");
+        assert!(!notify("READY=1").unwrap());
+
+        env::set_var("NOTIFY_SOCKET", &path);
+        let mut buf = [0; 64];
+        for state in ["READY=1", "WATCHDOG=1", "STOPPING=1"] {
+            assert!(notify(state).unwrap());
+            let n = socket.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..n], state.as_bytes());
+        }
+
+        env::set_var("NOTIFY_SOCKET", path.with_extension("missing"));
+        assert!(notify("READY=1").is_err());
+        env::remove_var("NOTIFY_SOCKET");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn watchdog_interval() {
+        env::set_var("WATCHDOG_USEC", "10000000");
+        env::remove_var("WATCHDOG_PID");
+        assert_eq!(watchdog(), Some(Duration::from_secs(5)));
+        env::set_var("WATCHDOG_PID", std::process::id().to_string());
+        assert_eq!(watchdog(), Some(Duration::from_secs(5)));
+        env::set_var("WATCHDOG_PID", (std::process::id() + 1).to_string());
+        assert_eq!(watchdog(), None);
+        env::set_var("WATCHDOG_PID", std::process::id().to_string());
+        env::set_var("WATCHDOG_USEC", "0");
+        assert_eq!(watchdog(), None);
+        env::remove_var("WATCHDOG_USEC");
+        env::remove_var("WATCHDOG_PID");
+        assert_eq!(watchdog(), None);
+    }
+}
@@ -1,22 +1,74 @@
+use crate::{cli::default, net::SocketError};
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
 use std::{
     io,
     net::{IpAddr, SocketAddr},
 };
 
+#[derive(Debug, Clone, Copy)]
+pub struct TcpOptions {
+    pub backlog: u32,
+    pub reuse_address: bool,
+    pub reuse_port: bool,
+    pub ipv6_only: bool,
+    pub nodelay: bool,
+}
+
+impl Default for TcpOptions {
+    fn default() -> Self {
+        TcpOptions {
+            backlog: default::backlog(),
+            reuse_address: default::reuse_address(),
+            reuse_port: default::reactors(),
+            ipv6_only: default::ipv6_only(),
+            nodelay: default::nodelay(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TcpSocket {
     socket: mio::net::TcpListener,
+    nodelay: bool,
 }
 
 impl TcpSocket {
-    pub fn new(addr: IpAddr, port: u16) -> TcpSocket {
+    pub fn new(addr: IpAddr, port: u16, options: &TcpOptions) -> Result<TcpSocket, SocketError> {
         let address = SocketAddr::new(addr, port);
-        let socket = mio::net::TcpListener::bind(address).unwrap();
+        let socket = match address {
+            SocketAddr::V4(_) => mio::net::TcpSocket::new_v4()?,
+            SocketAddr::V6(_) => {
+                let socket = mio::net::TcpSocket::new_v6()?;
+                set_ipv6_only(&socket, options.ipv6_only)?;
+                socket
+            }
+        };
+        socket.set_reuseaddr(options.reuse_address)?;
+        #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+        if options.reuse_port {
+            socket.set_reuseport(true)?;
+        }
+        socket.bind(address)?;
+        let socket = socket.listen(options.backlog)?;
 
-        TcpSocket { socket }
+        Ok(TcpSocket {
+            socket,
+            nodelay: options.nodelay,
+        })
+    }
+    pub fn from_std(socket: std::net::TcpListener, nodelay: bool) -> TcpSocket {
+        TcpSocket {
+            socket: mio::net::TcpListener::from_std(socket),
+            nodelay,
+        }
     }
     pub fn accept(&self) -> Result<(mio::net::TcpStream, SocketAddr), std::io::Error> {
-        self.socket.accept()
+        let (stream, address) = self.socket.accept()?;
+        if self.nodelay {
+            stream.set_nodelay(true)?;
+        }
+        Ok((stream, address))
     }
     pub fn socket(&self) -> &mio::net::TcpListener {
         &self.socket
@@ -48,16 +100,42 @@ impl mio::event::Source for TcpSocket {
     }
 }
 
+#[cfg(unix)]
+fn set_ipv6_only(socket: &mio::net::TcpSocket, ipv6_only: bool) -> io::Result<()> {
+    let value = ipv6_only as libc::c_int;
+    let result = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_IPV6,
+            libc::IPV6_V6ONLY,
+            &value as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if result == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_ipv6_only(_socket: &mio::net::TcpSocket, ipv6_only: bool) -> io::Result<()> {
+    if !ipv6_only {
+        log::warn!("dual-stack sockets are not supported on this platform, binding ipv6 only");
+    }
+    Ok(())
+}
+
 #[derive(Debug)]
 pub struct SimpleTcpSocket {
     socket: std::net::TcpListener,
 }
 
 impl SimpleTcpSocket {
-    pub fn new(addr: IpAddr, port: u16) -> SimpleTcpSocket {
+    pub fn new(addr: IpAddr, port: u16) -> Result<SimpleTcpSocket, SocketError> {
         let address = SocketAddr::new(addr, port);
-        let socket = std::net::TcpListener::bind(address).unwrap();
-        SimpleTcpSocket { socket }
+        let socket = std::net::TcpListener::bind(address)?;
+        Ok(SimpleTcpSocket { socket })
     }
     pub fn incoming(&mut self) -> std::net::Incoming {
         self.socket.incoming()
@@ -17,14 +17,14 @@ pub struct UdpSocket {
 }
 
 impl UdpSocket {
-    pub fn new(addr: IpAddr, port: u16) -> UdpSocket {
+    pub fn new(addr: IpAddr, port: u16) -> Result<UdpSocket, SocketError> {
         let address = SocketAddr::new(addr, port);
-        let socket = std::net::UdpSocket::bind(address).unwrap();
+        let socket = std::net::UdpSocket::bind(address)?;
 
-        UdpSocket {
+        Ok(UdpSocket {
             socket,
             remote_address: None,
-        }
+        })
     }
 }
 
@@ -0,0 +1,110 @@
+use log::{debug, warn};
+use std::{
+    fs, io,
+    os::unix::fs::{FileTypeExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug)]
+pub struct UnixSocket {
+    socket: mio::net::UnixListener,
+    path: PathBuf,
+    owned: bool,
+}
+
+impl UnixSocket {
+    pub fn new(path: &Path, mode: Option<u32>) -> io::Result<UnixSocket> {
+        remove_stale(path)?;
+        let socket = mio::net::UnixListener::bind(path)?;
+        if let Some(mode) = mode {
+            fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+        }
+
+        Ok(UnixSocket {
+            socket,
+            path: path.to_path_buf(),
+            owned: true,
+        })
+    }
+    pub fn from_std(socket: std::os::unix::net::UnixListener) -> io::Result<UnixSocket> {
+        let path = match socket.local_addr()?.as_pathname() {
+            Some(v) => v.to_path_buf(),
+            None => PathBuf::from("(unnamed)"),
+        };
+        socket.set_nonblocking(true)?;
+
+        Ok(UnixSocket {
+            socket: mio::net::UnixListener::from_std(socket),
+            path,
+            owned: false,
+        })
+    }
+    pub fn accept(&self) -> io::Result<(mio::net::UnixStream, mio::net::SocketAddr)> {
+        self.socket.accept()
+    }
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    pub fn socket(&self) -> &mio::net::UnixListener {
+        &self.socket
+    }
+    pub fn socket_mut(&mut self) -> &mut mio::net::UnixListener {
+        &mut self.socket
+    }
+}
+
+impl Drop for UnixSocket {
+    fn drop(&mut self) {
+        if !self.owned {
+            return;
+        }
+        debug!("removing unix socket: {:?}", &self.path);
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl mio::event::Source for UnixSocket {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        self.socket.register(registry, token, interests)
+    }
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        self.socket.reregister(registry, token, interests)
+    }
+    fn deregister(&mut self, registry: &mio::Registry) -> io::Result<()> {
+        self.socket.deregister(registry)
+    }
+}
+
+fn remove_stale(path: &Path) -> io::Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(v) => v,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{:?} exists and is not a socket", path),
+        ));
+    }
+    match std::os::unix::net::UnixStream::connect(path) {
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("{:?} is in use by another process", path),
+        )),
+        Err(_) => {
+            warn!("removing stale unix socket: {:?}", path);
+            fs::remove_file(path)
+        }
+    }
+}
@@ -1,32 +1,170 @@
-use crate::srv::ConnectionError;
-use log::trace;
-use std::io::{Read, Write};
+use crate::{
+    cli::{Builder, ServerConfig},
+    net::{Listener, Stream},
+    srv::{ConnectionError, Limits, Phase, Problem, ServerError, Timeouts, Transport},
+    web::{
+        content_length, header_len, https::sni, HttpParseError, HttpResponse, HttpStatus,
+        PeerCertificate, SessionStats, TlsConfig, TlsConfigBuilder, ToBuffer,
+    },
+};
+use log::{debug, trace};
+use std::{
+    collections::BTreeMap,
+    io::{Read, Write},
+    sync::Arc,
+    time::Instant,
+};
 
-pub struct Connection {
-    socket: mio::net::TcpStream,
+const MAX_HELLO: usize = 16 * 1024 + 5;
+
+fn next_request(rbuf: &mut Vec<u8>, limits: &Limits) -> Result<Option<Vec<u8>>, HttpParseError> {
+    let header_len = match header_len(rbuf) {
+        Some(v) if v > limits.header => return Err(HttpParseError::HeaderSize(v)),
+        Some(v) => v,
+        None if rbuf.len() > limits.header => return Err(HttpParseError::HeaderSize(rbuf.len())),
+        None => return Ok(None),
+    };
+    let content_length = content_length(&rbuf[..header_len])?;
+    if content_length > limits.body {
+        return Err(HttpParseError::BodySize(content_length));
+    }
+    if rbuf.len() >= header_len + content_length {
+        Ok(Some(rbuf.drain(..header_len + content_length).collect()))
+    } else {
+        Ok(None)
+    }
+}
+
+fn input_phase(rbuf: &[u8], served: bool) -> Phase {
+    if rbuf.is_empty() {
+        if served {
+            Phase::Idle
+        } else {
+            Phase::Header
+        }
+    } else if header_len(rbuf).is_none() {
+        Phase::Header
+    } else {
+        Phase::Body
+    }
+}
+
+struct Capture<'a> {
+    socket: &'a mut Stream,
+    buf: &'a mut Vec<u8>,
+}
+
+impl Read for Capture<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = self.socket.read(buf)?;
+        self.buf.extend_from_slice(&buf[..size]);
+        Ok(size)
+    }
+}
+
+fn handshake_error(e: rustls::Error, hello: Option<&[u8]>) -> ConnectionError {
+    use rustls::{internal::msgs::enums::AlertDescription, Error};
+
+    if let Some(e) = hello
+        .and_then(sni::names)
+        .and_then(|v| server_name_error(&v))
+    {
+        return ConnectionError::TlsServerName(e);
+    }
+    match e {
+        Error::PeerIncompatibleError(_) | Error::CorruptMessage => {
+            ConnectionError::TlsProtocolMismatch(e)
+        }
+        Error::AlertReceived(
+            AlertDescription::ProtocolVersion
+            | AlertDescription::HandshakeFailure
+            | AlertDescription::InsufficientSecurity,
+        ) => ConnectionError::TlsProtocolMismatch(e),
+        Error::AlertReceived(AlertDescription::UnrecognisedName) => {
+            ConnectionError::TlsServerName(e.to_string())
+        }
+        Error::AlertReceived(
+            AlertDescription::UnknownCA
+            | AlertDescription::BadCertificate
+            | AlertDescription::CertificateUnknown,
+        ) => ConnectionError::TlsUnknownCa(e),
+        e => ConnectionError::TlsHandshake(e),
+    }
+}
+
+fn server_name_error(names: &[(u8, Vec<u8>)]) -> Option<String> {
+    let hosts: Vec<&[u8]> = names
+        .iter()
+        .filter(|(kind, _)| *kind == 0)
+        .map(|(_, name)| name.as_slice())
+        .collect();
+    match hosts.as_slice() {
+        [] => Some(String::from("server name extension without a host name")),
+        [name] if webpki::DnsNameRef::try_from_ascii(name).is_err() => {
+            Some(format!("invalid name {:?}", String::from_utf8_lossy(name)))
+        }
+        [_] => None,
+        _ => Some(String::from(
+            "server name extension with duplicate host names",
+        )),
+    }
+}
+
+fn status_buf(status: HttpStatus) -> Vec<u8> {
+    let mut response = HttpResponse::from(status);
+    response.add_header(String::from("Connection"), String::from("close"));
+    response.to_buf()
+}
+
+pub struct ConnectionState {
+    socket: Stream,
     token: mio::Token,
+    generation: usize,
+    rbuf: Vec<u8>,
     wbuf: Vec<u8>,
+    woff: usize,
+    seq: usize,
+    wseq: usize,
+    pending: BTreeMap<usize, (Vec<u8>, bool)>,
+    phase: Phase,
+    deadline: Option<Instant>,
+    progress: bool,
+    eof: bool,
     closing: bool,
     closed: bool,
 }
 
-impl Connection {
-    pub fn new(socket: mio::net::TcpStream, token: mio::Token) -> Self {
-        Connection {
+impl ConnectionState {
+    pub fn new(socket: Stream, token: mio::Token, generation: usize) -> Self {
+        ConnectionState {
             socket,
             token,
+            generation,
+            rbuf: Vec::new(),
             wbuf: Vec::new(),
+            woff: 0,
+            seq: 0,
+            wseq: 0,
+            pending: BTreeMap::new(),
+            phase: Phase::Header,
+            deadline: None,
+            progress: false,
+            eof: false,
             closing: false,
             closed: false,
         }
     }
-    pub fn shutdown(&mut self, how: std::net::Shutdown, registry: &mio::Registry) {
-        match self.socket.shutdown(how) {
-            _ => {
-                self.closed = true;
-                self.deregister(registry);
-            }
-        }
+    pub fn token(&self) -> mio::Token {
+        self.token
+    }
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
     }
     pub fn is_closing(&self) -> bool {
         self.closing
@@ -34,119 +172,262 @@ impl Connection {
     pub fn is_closed(&self) -> bool {
         self.closed
     }
-    pub fn register(&mut self, registry: &mio::Registry) {
-        let interest = mio::Interest::READABLE | mio::Interest::WRITABLE;
+    pub fn refresh(&mut self, phase: Phase, timeouts: &Timeouts, now: Instant) -> Option<Instant> {
+        let progress = std::mem::take(&mut self.progress);
+        if phase == self.phase && self.deadline.is_some() && !progress {
+            return None;
+        }
+        trace!("connection {:?} in phase {:?}", self.token, phase);
+        self.phase = phase;
+        self.deadline = timeouts.get(phase).map(|v| now + v);
+        self.deadline
+    }
+    pub fn next_request(&mut self, limits: &Limits) -> Result<Option<Vec<u8>>, HttpParseError> {
+        next_request(&mut self.rbuf, limits)
+    }
+    pub fn next_seq(&mut self) -> usize {
+        self.seq += 1;
+        self.seq - 1
+    }
+    pub fn complete(&mut self, seq: usize, buf: Vec<u8>, close: bool) {
+        if self.closing {
+            trace!("discarding response {} for closing connection", seq);
+            return;
+        }
+        self.pending.insert(seq, (buf, close));
+        while let Some((mut buf, close)) = self.pending.remove(&self.wseq) {
+            trace!(
+                "queued response {} for connection {:?}",
+                self.wseq,
+                self.token
+            );
+            self.wbuf.append(&mut buf);
+            self.wseq += 1;
+            if close {
+                self.close_after_flush();
+                break;
+            }
+        }
+    }
+    pub fn register(
+        &mut self,
+        registry: &mio::Registry,
+        interest: mio::Interest,
+    ) -> Result<(), ConnectionError> {
         registry
             .register(&mut self.socket, self.token, interest)
-            .unwrap();
+            .map_err(ConnectionError::Register)
     }
-    pub fn reregister(&mut self, registry: &mio::Registry) {
-        let interest = mio::Interest::READABLE | mio::Interest::WRITABLE;
+    pub fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        interest: mio::Interest,
+    ) -> Result<(), ConnectionError> {
         registry
             .reregister(&mut self.socket, self.token, interest)
-            .unwrap();
+            .map_err(ConnectionError::Register)
     }
-    fn deregister(&mut self, registry: &mio::Registry) {
-        registry.deregister(&mut self.socket).unwrap();
+    pub fn shutdown(&mut self, how: std::net::Shutdown, registry: &mio::Registry) {
+        if self.closed {
+            return;
+        }
+        let _ = self.socket.shutdown(how);
+        self.closed = true;
+        if let Err(e) = registry.deregister(&mut self.socket) {
+            debug!("error deregistering connection {:?}: {:?}", self.token, e);
+        }
+    }
+    fn close_after_flush(&mut self) {
+        trace!("closing connection {:?} after flush", self.token);
+        self.closing = true;
+        self.pending.clear();
+        self.seq = self.wseq;
+    }
+    fn has_output(&self) -> bool {
+        self.woff < self.wbuf.len()
+    }
+    fn current_phase(&self, writing: bool) -> Phase {
+        if writing || self.has_output() {
+            Phase::Write
+        } else if self.seq != self.wseq {
+            Phase::Processing
+        } else {
+            input_phase(&self.rbuf, self.seq > 0)
+        }
     }
-    pub fn read(&mut self) -> Result<Vec<u8>, ConnectionError> {
-        let mut bufsiz: usize = 1024;
-        let mut buffer: Vec<u8> = vec![];
+    fn flushed(&self) -> bool {
+        (self.closing || self.eof) && self.seq == self.wseq && !self.has_output()
+    }
+}
+
+pub struct Connection {
+    state: ConnectionState,
+}
+
+impl Transport for Connection {
+    type Config = ();
+
+    fn config(_config: &ServerConfig, _listener: &Listener) -> Result<(), ServerError> {
+        Ok(())
+    }
+    fn check(_config: &ServerConfig, _listener: &Listener) -> Vec<Problem> {
+        Vec::new()
+    }
+    fn reload(_config: &mut (), _now: Instant) -> Option<Instant> {
+        None
+    }
+    fn accept(
+        socket: Stream,
+        token: mio::Token,
+        generation: usize,
+        _config: &(),
+    ) -> Result<Self, ConnectionError> {
+        Ok(Connection {
+            state: ConnectionState::new(socket, token, generation),
+        })
+    }
+    fn refuse(mut socket: Stream) {
+        let _ = socket.write(&status_buf(HttpStatus::ServiceUnavailable));
+        let _ = socket.shutdown(std::net::Shutdown::Both);
+    }
+    fn state(&self) -> &ConnectionState {
+        &self.state
+    }
+    fn state_mut(&mut self) -> &mut ConnectionState {
+        &mut self.state
+    }
+    fn interest(&self) -> mio::Interest {
+        if self.state.has_output() {
+            mio::Interest::READABLE | mio::Interest::WRITABLE
+        } else {
+            mio::Interest::READABLE
+        }
+    }
+    fn current_phase(&self) -> Phase {
+        self.state.current_phase(false)
+    }
+    fn peer(&mut self) -> Option<PeerCertificate> {
+        None
+    }
+    fn is_finished(&self) -> bool {
+        self.state.flushed()
+    }
+    fn read(&mut self) -> Result<usize, ConnectionError> {
+        let state = &mut self.state;
+        let mut buf = [0; 4096];
+        let mut total = 0;
         loop {
-            let mut buf: Vec<u8> = vec![0; bufsiz];
-            match self.socket.read(&mut buf) {
-                Ok(0) => return Ok(buffer),
+            match state.socket.read(&mut buf) {
+                Ok(0) => {
+                    trace!("connection closed by peer: {:?}", state.token);
+                    state.eof = true;
+                    return Ok(total);
+                }
                 Ok(size) => {
-                    buffer.append(&mut buf);
-                    if size == bufsiz {
-                        bufsiz *= 2;
-                    } else {
-                        return Ok(buffer);
-                    }
+                    state.rbuf.extend_from_slice(&buf[..size]);
+                    total += size;
                 }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(total),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
                 Err(e) => return Err(ConnectionError::PlainRead(e)),
             }
         }
     }
-    pub fn write_b(&mut self, buf: Vec<u8>) -> std::io::Result<usize> {
-        self.wbuf.write(&buf)
-    }
-    pub fn write(&mut self) -> Result<usize, ConnectionError> {
-        match self.socket.write(&self.wbuf) {
-            Ok(size) => {
-                trace!("write plaintext to session: {} bytes", size);
-                self.wbuf.clear();
-                Ok(size)
+    fn write(&mut self) -> Result<usize, ConnectionError> {
+        let state = &mut self.state;
+        let mut total = 0;
+        while state.has_output() {
+            match state.socket.write(&state.wbuf[state.woff..]) {
+                Ok(0) => {
+                    return Err(ConnectionError::PlainWrite(std::io::Error::from(
+                        std::io::ErrorKind::WriteZero,
+                    )))
+                }
+                Ok(size) => {
+                    trace!("write plaintext to session: {} bytes", size);
+                    state.woff += size;
+                    total += size;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(ConnectionError::PlainWrite(e)),
             }
-            Err(e) => Err(ConnectionError::PlainWrite(e)),
+        }
+        if !state.has_output() {
+            state.wbuf.clear();
+            state.woff = 0;
+        }
+        state.progress |= total > 0;
+        Ok(total)
+    }
+    fn reject(&mut self, status: HttpStatus, registry: &mio::Registry) {
+        trace!(
+            "rejecting connection {:?} with {}",
+            self.state.token,
+            status
+        );
+        let _ = self.state.socket.write(&status_buf(status));
+        self.shutdown(std::net::Shutdown::Both, registry);
+    }
+    fn expire(&mut self, registry: &mio::Registry) {
+        match self.state.phase {
+            Phase::Header | Phase::Body => self.reject(HttpStatus::RequestTimeout, registry),
+            Phase::Processing => self.reject(HttpStatus::GatewayTimeout, registry),
+            _ => self.shutdown(std::net::Shutdown::Both, registry),
         }
     }
 }
 
 pub struct SecureConnection {
-    socket: mio::net::TcpStream,
-    token: mio::Token,
+    state: ConnectionState,
     tls_conn: rustls::ServerConnection,
-    closing: bool,
-    closed: bool,
+    peer: Option<Option<PeerCertificate>>,
+    hello: Option<Vec<u8>>,
+    handshaken: bool,
+    notified: bool,
+    stats: Arc<SessionStats>,
 }
 
 impl SecureConnection {
     pub fn new(
-        socket: mio::net::TcpStream,
+        socket: Stream,
         token: mio::Token,
+        generation: usize,
         tls_conn: rustls::ServerConnection,
+        stats: Arc<SessionStats>,
     ) -> Self {
         SecureConnection {
-            socket,
-            token,
+            state: ConnectionState::new(socket, token, generation),
             tls_conn,
-            closing: false,
-            closed: false,
+            peer: None,
+            hello: Some(Vec::new()),
+            handshaken: false,
+            notified: false,
+            stats,
         }
     }
-    pub fn shutdown(&mut self, how: std::net::Shutdown, registry: &mio::Registry) {
-        match self.socket.shutdown(how) {
-            _ => {
-                self.closed = true;
-                self.deregister(registry);
-            }
+    fn close_notify(&mut self) {
+        if self.notified || self.tls_conn.is_handshaking() {
+            return;
         }
+        trace!("sending close_notify to connection {:?}", self.state.token);
+        self.tls_conn.send_close_notify();
+        self.notified = true;
     }
-    pub fn is_closing(&self) -> bool {
-        self.closing
-    }
-    pub fn is_closed(&self) -> bool {
-        self.closed
-    }
-    pub fn register(&mut self, registry: &mio::Registry) {
-        let interest = self.interest();
-        registry
-            .register(&mut self.socket, self.token, interest)
-            .unwrap();
-    }
-    pub fn reregister(&mut self, registry: &mio::Registry) {
-        let interest = self.interest();
-        registry
-            .reregister(&mut self.socket, self.token, interest)
-            .unwrap();
-    }
-    fn deregister(&mut self, registry: &mio::Registry) {
-        registry.deregister(&mut self.socket).unwrap();
-    }
-    fn interest(&self) -> mio::Interest {
-        let read = self.tls_conn.wants_read();
-        let write = self.tls_conn.wants_write();
-        if read && write {
-            mio::Interest::READABLE | mio::Interest::WRITABLE
-        } else if read {
-            mio::Interest::READABLE
-        } else {
-            mio::Interest::WRITABLE
-        }
-    }
-    pub fn read_tls(&mut self) -> Result<usize, ConnectionError> {
-        match self.tls_conn.read_tls(&mut self.socket) {
+    fn read_tls(&mut self) -> Result<usize, ConnectionError> {
+        let read = match &mut self.hello {
+            Some(buf) if buf.len() < MAX_HELLO => self.tls_conn.read_tls(&mut Capture {
+                socket: &mut self.state.socket,
+                buf,
+            }),
+            _ => self.tls_conn.read_tls(&mut self.state.socket),
+        };
+        match read {
+            Ok(0) => {
+                trace!("connection closed by peer: {:?}", self.state.token);
+                self.state.eof = true;
+                Ok(0)
+            }
             Ok(size) => {
                 trace!("read tls from socket: {} bytes", size);
                 Ok(size)
@@ -154,41 +435,331 @@ impl SecureConnection {
             Err(e) => Err(ConnectionError::TlsRead(e)),
         }
     }
-    pub fn process_tls(&mut self) -> Result<rustls::IoState, ConnectionError> {
+    fn process_tls(&mut self) -> Result<rustls::IoState, ConnectionError> {
         match self.tls_conn.process_new_packets() {
             Ok(v) => {
                 trace!("successfully processed new tls packets");
+                if self.tls_conn.wants_write() || !self.tls_conn.is_handshaking() {
+                    self.hello = None;
+                }
+                if !self.handshaken && !self.tls_conn.is_handshaking() {
+                    self.handshaken = true;
+                    self.stats.handshake();
+                    self.negotiated();
+                }
                 Ok(v)
             }
-            Err(e) => Err(ConnectionError::TlsProcess(e)),
+            Err(e) => {
+                let _ = self.tls_conn.write_tls(&mut self.state.socket);
+                if self.handshaken {
+                    return Err(ConnectionError::TlsProcess(e));
+                }
+                let e = handshake_error(e, self.hello.take().as_deref());
+                self.stats.failure(&e);
+                Err(e)
+            }
         }
     }
-    pub fn read_plain(&mut self, size: usize) -> Result<Vec<u8>, ConnectionError> {
+    fn negotiated(&self) {
+        debug!(
+            "negotiated {:?} {:?} alpn {:?} sni {:?} for connection {:?}",
+            self.tls_conn.protocol_version(),
+            self.tls_conn.negotiated_cipher_suite().map(|v| v.suite()),
+            self.tls_conn.alpn_protocol().map(String::from_utf8_lossy),
+            self.tls_conn.sni_hostname(),
+            self.state.token
+        );
+    }
+    fn read_plain(&mut self, size: usize) -> Result<usize, ConnectionError> {
         let mut buf: Vec<u8> = vec![0; size];
         match self.tls_conn.reader().read(&mut buf) {
             Ok(size) => {
                 trace!("read plaintext from session: {} bytes", size);
-                Ok(buf)
+                self.state.rbuf.extend_from_slice(&buf[..size]);
+                Ok(size)
             }
             Err(e) => Err(ConnectionError::PlainRead(e)),
         }
     }
-    pub fn write_plain(&mut self, buf: Vec<u8>) -> Result<usize, ConnectionError> {
-        match self.tls_conn.writer().write(&buf) {
+    fn write_plain(&mut self) -> Result<usize, ConnectionError> {
+        if !self.state.has_output() {
+            return Ok(0);
+        }
+        match self
+            .tls_conn
+            .writer()
+            .write(&self.state.wbuf[self.state.woff..])
+        {
             Ok(size) => {
                 trace!("write plaintext to session: {} bytes", size);
+                self.state.woff += size;
+                if !self.state.has_output() {
+                    self.state.wbuf.clear();
+                    self.state.woff = 0;
+                }
                 Ok(size)
             }
             Err(e) => Err(ConnectionError::PlainWrite(e)),
         }
     }
-    pub fn write_tls(&mut self) -> Result<usize, ConnectionError> {
-        match self.tls_conn.write_tls(&mut self.socket) {
-            Ok(size) => {
-                trace!("write tls to socket: {} bytes", size);
-                Ok(size)
+}
+
+impl Transport for SecureConnection {
+    type Config = TlsConfig;
+
+    fn config(config: &ServerConfig, listener: &Listener) -> Result<TlsConfig, ServerError> {
+        let tls_config = TlsConfig::new(TlsConfigBuilder::new(config, listener))?;
+        Ok(tls_config)
+    }
+    fn check(config: &ServerConfig, listener: &Listener) -> Vec<Problem> {
+        TlsConfigBuilder::new(config, listener)
+            .problems()
+            .into_iter()
+            .map(|(options, e)| (options, ServerError::from(e)))
+            .collect()
+    }
+    fn reload(config: &mut TlsConfig, now: Instant) -> Option<Instant> {
+        Some(config.reload(now))
+    }
+    fn accept(
+        socket: Stream,
+        token: mio::Token,
+        generation: usize,
+        config: &TlsConfig,
+    ) -> Result<Self, ConnectionError> {
+        let tls_conn =
+            rustls::ServerConnection::new(config.current()).map_err(ConnectionError::TlsProcess)?;
+        Ok(SecureConnection::new(
+            socket,
+            token,
+            generation,
+            tls_conn,
+            config.stats(),
+        ))
+    }
+    fn refuse(socket: Stream) {
+        let _ = socket.shutdown(std::net::Shutdown::Both);
+    }
+    fn state(&self) -> &ConnectionState {
+        &self.state
+    }
+    fn state_mut(&mut self) -> &mut ConnectionState {
+        &mut self.state
+    }
+    fn interest(&self) -> mio::Interest {
+        let read = self.tls_conn.wants_read();
+        let write = self.tls_conn.wants_write() || self.state.has_output();
+        if read && write {
+            mio::Interest::READABLE | mio::Interest::WRITABLE
+        } else if read {
+            mio::Interest::READABLE
+        } else {
+            mio::Interest::WRITABLE
+        }
+    }
+    fn current_phase(&self) -> Phase {
+        self.state.current_phase(self.tls_conn.wants_write())
+    }
+    fn peer(&mut self) -> Option<PeerCertificate> {
+        let tls_conn = &self.tls_conn;
+        self.peer
+            .get_or_insert_with(|| {
+                let peer = tls_conn
+                    .peer_certificates()
+                    .and_then(|v| v.first())
+                    .and_then(|v| PeerCertificate::parse(&v.0));
+                if let Some(v) = &peer {
+                    debug!("client certificate {} {:?}", v.subject, v.sans);
+                }
+                peer
+            })
+            .clone()
+    }
+    fn is_finished(&self) -> bool {
+        self.state.flushed()
+            && !self.tls_conn.wants_write()
+            && (self.notified || self.tls_conn.is_handshaking())
+    }
+    fn read(&mut self) -> Result<usize, ConnectionError> {
+        let mut total = 0;
+        while !self.state.eof {
+            match self.read_tls() {
+                Ok(_) => {}
+                Err(ConnectionError::TlsRead(e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    break
+                }
+                Err(ConnectionError::TlsRead(e)) if e.kind() == std::io::ErrorKind::Interrupted => {
+                    continue
+                }
+                Err(e) => return Err(e),
+            }
+            let io_state = self.process_tls()?;
+            if io_state.plaintext_bytes_to_read() > 0 {
+                total += self.read_plain(io_state.plaintext_bytes_to_read())?;
+            }
+            if io_state.peer_has_closed() {
+                self.state.eof = true;
+            }
+        }
+        Ok(total)
+    }
+    fn write(&mut self) -> Result<usize, ConnectionError> {
+        let mut total = 0;
+        loop {
+            self.write_plain()?;
+            if self.state.flushed() {
+                self.close_notify();
+            }
+            if !self.tls_conn.wants_write() {
+                break;
+            }
+            match self.tls_conn.write_tls(&mut self.state.socket) {
+                Ok(0) => break,
+                Ok(size) => {
+                    trace!("write tls to socket: {} bytes", size);
+                    total += size;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) if self.notified && self.state.eof => {
+                    trace!("peer closed before close_notify was sent: {:?}", e);
+                    break;
+                }
+                Err(e) => return Err(ConnectionError::TlsWrite(e)),
+            }
+        }
+        self.state.progress |= total > 0;
+        Ok(total)
+    }
+    fn reject(&mut self, status: HttpStatus, registry: &mio::Registry) {
+        trace!(
+            "rejecting connection {:?} with {}",
+            self.state.token,
+            status
+        );
+        if self
+            .tls_conn
+            .writer()
+            .write_all(&status_buf(status))
+            .is_ok()
+        {
+            self.close_notify();
+            let _ = self.tls_conn.write_tls(&mut self.state.socket);
+        }
+        self.shutdown(std::net::Shutdown::Both, registry);
+    }
+    fn expire(&mut self, registry: &mio::Registry) {
+        match self.state.phase {
+            Phase::Header | Phase::Body if !self.tls_conn.is_handshaking() => {
+                self.reject(HttpStatus::RequestTimeout, registry)
+            }
+            Phase::Processing => self.reject(HttpStatus::GatewayTimeout, registry),
+            Phase::Idle => {
+                self.close_notify();
+                let _ = self.tls_conn.write_tls(&mut self.state.socket);
+                self.shutdown(std::net::Shutdown::Both, registry)
+            }
+            _ => self.shutdown(std::net::Shutdown::Both, registry),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::https::testdata::{cert, read};
+    use rustls::{Certificate, ClientConnection, PrivateKey, ServerConnection};
+
+    fn server() -> ServerConnection {
+        let config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![Certificate(cert("leaf.pem")), Certificate(cert("ca.pem"))],
+                PrivateKey(read("pkcs8.der")),
+            )
+            .unwrap();
+        ServerConnection::new(Arc::new(config)).unwrap()
+    }
+
+    fn client(ca: &str) -> ClientConnection {
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(&Certificate(cert(ca))).unwrap();
+        let config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        ClientConnection::new(Arc::new(config), "localhost".try_into().unwrap()).unwrap()
+    }
+
+    fn records(
+        conn: &mut impl std::ops::DerefMut<Target = rustls::ConnectionCommon<impl Sized>>,
+    ) -> Vec<u8> {
+        let mut buf = Vec::new();
+        while conn.wants_write() {
+            conn.write_tls(&mut buf).unwrap();
+        }
+        buf
+    }
+
+    fn handshake(hello: &[u8]) -> ConnectionError {
+        let mut server = server();
+        server.read_tls(&mut &hello[..]).unwrap();
+        let e = server.process_new_packets().unwrap_err();
+        handshake_error(e, Some(hello))
+    }
+
+    fn patched_hello(patch: impl Fn(&mut Vec<u8>, usize)) -> Vec<u8> {
+        let mut hello = records(&mut client("ca.pem"));
+        let at = hello.windows(9).position(|v| v == b"localhost").unwrap();
+        patch(&mut hello, at);
+        hello
+    }
+
+    #[test]
+    fn valid_server_name_is_accepted() {
+        let hello = records(&mut client("ca.pem"));
+        let mut server = server();
+        server.read_tls(&mut &hello[..]).unwrap();
+        assert!(server.process_new_packets().is_ok());
+        assert_eq!(server.sni_hostname(), Some("localhost"));
+    }
+
+    #[test]
+    fn invalid_server_name_is_reported() {
+        let hello = patched_hello(|v, at| v[at + 8] = b'!');
+        match handshake(&hello) {
+            ConnectionError::TlsServerName(e) => assert_eq!(e, "invalid name \"localhos!\""),
+            e => panic!("expected a server name error, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn server_name_without_host_name_is_reported() {
+        let hello = patched_hello(|v, at| v[at - 3] = 1);
+        match handshake(&hello) {
+            ConnectionError::TlsServerName(e) => {
+                assert_eq!(e, "server name extension without a host name")
             }
-            Err(e) => Err(ConnectionError::TlsWrite(e)),
+            e => panic!("expected a server name error, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn unknown_ca_alert_is_reported() {
+        let (mut client, mut server) = (client("other-ca.pem"), server());
+        let mut result = Ok(());
+        while result.is_ok() {
+            let buf = records(&mut client);
+            server.read_tls(&mut &buf[..]).unwrap();
+            result = server.process_new_packets().map(|_| ());
+            let buf = records(&mut server);
+            client.read_tls(&mut &buf[..]).unwrap();
+            let _ = client.process_new_packets();
+        }
+        match handshake_error(result.unwrap_err(), None) {
+            ConnectionError::TlsUnknownCa(_) => {}
+            e => panic!("expected an unknown ca error, got {:?}", e),
         }
     }
 }
@@ -0,0 +1,104 @@
+use crate::{
+    srv::{Router, Server, ServerError, SERVER_WAKER_TOKEN},
+    syn::{ThreadPool, ThreadPoolError},
+    web::{HttpResponse, HttpStatus, PeerCertificate, ToBuffer},
+};
+use log::{debug, error};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    sync::{mpsc, Arc},
+};
+
+#[derive(Debug)]
+pub struct Completion {
+    pub token: mio::Token,
+    pub generation: usize,
+    pub seq: usize,
+    pub buf: Vec<u8>,
+    pub close: bool,
+}
+
+pub struct Dispatcher {
+    threads: ThreadPool,
+    router: Arc<Router>,
+    waker: Arc<mio::Waker>,
+    sender: mpsc::Sender<Completion>,
+    receiver: mpsc::Receiver<Completion>,
+}
+
+impl Dispatcher {
+    pub fn new(
+        threads: ThreadPool,
+        router: Router,
+        registry: &mio::Registry,
+    ) -> std::io::Result<Dispatcher> {
+        let waker = Arc::new(mio::Waker::new(registry, SERVER_WAKER_TOKEN)?);
+        let (sender, receiver) = mpsc::channel::<Completion>();
+        Ok(Dispatcher {
+            threads,
+            router: Arc::new(router),
+            waker,
+            sender,
+            receiver,
+        })
+    }
+    pub fn dispatch<S>(
+        &self,
+        token: mio::Token,
+        generation: usize,
+        seq: usize,
+        mut buf: Vec<u8>,
+        root: &Path,
+        peer: Option<PeerCertificate>,
+    ) -> Result<(), ThreadPoolError>
+    where
+        S: Server<S, ServerError> + 'static,
+    {
+        let sender = self.sender.clone();
+        let waker = Arc::clone(&self.waker);
+        let root = root.to_path_buf();
+        let router = Arc::clone(&self.router);
+        self.threads.execute(move || {
+            let respond = AssertUnwindSafe(|| match S::request(&mut buf) {
+                Ok(mut request) => {
+                    request.peer = peer;
+                    let response = router.respond::<S>(&request, &root);
+                    (response, request.is_close())
+                }
+                Err(e) => {
+                    error!("error parsing request: {:?}", e);
+                    (HttpResponse::from(HttpStatus::BadRequest), true)
+                }
+            });
+            let (mut response, close) = panic::catch_unwind(respond).unwrap_or_else(|_| {
+                error!("request {} for connection {:?} panicked", seq, token);
+                (HttpResponse::from(HttpStatus::InternalServerError), true)
+            });
+            if close {
+                response.add_header(String::from("Connection"), String::from("close"));
+            }
+            let completion = Completion {
+                token,
+                generation,
+                seq,
+                buf: response.to_buf(),
+                close,
+            };
+            debug!("completed request {} for connection {:?}", seq, token);
+            if sender.send(completion).is_err() {
+                error!("event loop is gone, dropping response for {:?}", token);
+                return;
+            }
+            if let Err(e) = waker.wake() {
+                error!("error waking event loop: {:?}", e);
+            }
+        })
+    }
+    pub fn completions(&self) -> mpsc::TryIter<'_, Completion> {
+        self.receiver.try_iter()
+    }
+    pub fn size(&self) -> usize {
+        self.threads.size()
+    }
+}
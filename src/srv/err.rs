@@ -1,26 +1,92 @@
-use crate::web::{HttpParseError, HttpResponseError, TlsConfigError};
+use crate::{
+    net::SocketError,
+    srv::timer::Phase,
+    syn::ThreadPoolError,
+    web::{HttpParseError, HttpResponseError, TlsConfigError},
+};
+use std::fmt;
 
 #[derive(Debug)]
 pub enum ConnectionError {
     TlsRead(std::io::Error),
     TlsProcess(rustls::Error),
+    TlsProtocolMismatch(rustls::Error),
+    TlsServerName(String),
+    TlsUnknownCa(rustls::Error),
+    TlsHandshake(rustls::Error),
     TlsWrite(std::io::Error),
     PlainRead(std::io::Error),
     PlainWrite(std::io::Error),
+    Register(std::io::Error),
+}
+
+impl ConnectionError {
+    pub fn is_handshake(&self) -> bool {
+        matches!(
+            self,
+            Self::TlsProtocolMismatch(_)
+                | Self::TlsServerName(_)
+                | Self::TlsUnknownCa(_)
+                | Self::TlsHandshake(_)
+        )
+    }
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TlsProtocolMismatch(e) => write!(f, "tls protocol mismatch: {}", e),
+            Self::TlsServerName(v) => write!(f, "bad tls server name: {}", v),
+            Self::TlsUnknownCa(e) => write!(f, "client rejected the certificate: {}", e),
+            Self::TlsHandshake(e) => write!(f, "tls handshake failed: {}", e),
+            e => write!(f, "{:?}", e),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum ServerRootError {
     Unknown,
+    NotFound(std::path::PathBuf),
+}
+
+#[derive(Debug)]
+pub enum RouteError {
+    RedirectPort(u16),
+    Header(String),
+    Root(std::path::PathBuf),
+    ErrorPage(std::path::PathBuf),
+}
+
+#[derive(Debug)]
+pub enum SlabError {
+    Capacity(usize),
+}
+
+#[derive(Debug)]
+pub enum LimitError {
+    HeaderSize(usize),
+}
+
+#[derive(Debug)]
+pub enum TimerError {
+    Zero(Phase),
 }
 
 #[derive(Debug)]
 pub enum ServerError {
+    Socket(SocketError),
+    Threads(ThreadPoolError),
+    Poll(std::io::Error),
     Request(HttpParseError),
     RequestIo(std::io::Error),
     Response(HttpResponseError),
     Security(TlsConfigError),
     RootPath(ServerRootError),
+    Route(RouteError),
+    Connections(SlabError),
+    Timeouts(TimerError),
+    Limits(LimitError),
     SessionIo(std::io::Error),
     Connection(ConnectionError),
 }
@@ -31,6 +97,18 @@ impl From<std::io::Error> for ServerError {
     }
 }
 
+impl From<SocketError> for ServerError {
+    fn from(e: SocketError) -> Self {
+        Self::Socket(e)
+    }
+}
+
+impl From<ThreadPoolError> for ServerError {
+    fn from(e: ThreadPoolError) -> Self {
+        Self::Threads(e)
+    }
+}
+
 impl From<HttpParseError> for ServerError {
     fn from(e: HttpParseError) -> Self {
         Self::Request(e)
@@ -55,8 +133,63 @@ impl From<ServerRootError> for ServerError {
     }
 }
 
+impl From<RouteError> for ServerError {
+    fn from(e: RouteError) -> Self {
+        Self::Route(e)
+    }
+}
+
+impl From<SlabError> for ServerError {
+    fn from(e: SlabError) -> Self {
+        Self::Connections(e)
+    }
+}
+
+impl From<TimerError> for ServerError {
+    fn from(e: TimerError) -> Self {
+        Self::Timeouts(e)
+    }
+}
+
+impl From<LimitError> for ServerError {
+    fn from(e: LimitError) -> Self {
+        Self::Limits(e)
+    }
+}
+
 impl From<ConnectionError> for ServerError {
     fn from(e: ConnectionError) -> Self {
         Self::Connection(e)
     }
 }
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Socket(e) => write!(f, "{}", e),
+            Self::Poll(e) => write!(f, "error setting up the event loop: {}", e),
+            Self::Security(e) => write!(f, "error loading tls configuration: {}", e),
+            Self::RootPath(ServerRootError::NotFound(v)) => {
+                write!(f, "server root is not a directory: {}", v.display())
+            }
+            Self::RootPath(e) => write!(f, "invalid server root: {:?}", e),
+            Self::Threads(ThreadPoolError::General(v)) => write!(f, "{}", v),
+            Self::Route(RouteError::RedirectPort(v)) => write!(f, "invalid redirect port: {}", v),
+            Self::Route(RouteError::Header(v)) => write!(f, "invalid response header: {:?}", v),
+            Self::Route(RouteError::Root(v)) => {
+                write!(f, "route root is not a directory: {}", v.display())
+            }
+            Self::Route(RouteError::ErrorPage(v)) => {
+                write!(f, "error page is not a file: {}", v.display())
+            }
+            Self::Connections(SlabError::Capacity(v)) => {
+                write!(f, "invalid connection limit: {}", v)
+            }
+            Self::Timeouts(TimerError::Zero(v)) => write!(f, "{:?} timeout must not be zero", v),
+            Self::Limits(LimitError::HeaderSize(v)) => {
+                write!(f, "invalid header size limit: {}", v)
+            }
+            e => write!(f, "{:?}", e),
+        }
+    }
+}
@@ -1,75 +1,83 @@
 use crate::{
-    cli::{Builder, CliOpt},
-    net::{SocketBuilder, TcpSocket},
+    cli::{Builder, ServerConfig},
+    net::{ListenSocket, Listener, SocketBuilder},
     srv::{
-        ConnectionHandler, Server, ServerError, ServerRootBuilder, SERVER_QUEUE_SIZE,
-        SERVER_SOCKET_TOKEN,
+        reactor::{self, Heartbeat, HEARTBEAT},
+        ConnectionError, ConnectionHandler, Dispatcher, Limits, LimitsBuilder, Problem,
+        RouteBuilder, Server, ServerError, ServerRootBuilder, Slab, SlabBuilder, Timeouts,
+        TimeoutsBuilder, Timers, Transport, SERVER_QUEUE_SIZE, SERVER_SOCKET_TOKEN,
+        SERVER_WAKER_TOKEN,
     },
     syn::ThreadPoolBuilder,
-    web::{HttpAdapter, HttpReceiver, HttpRequest, HttpResponder, HttpResponse, ToBuffer},
+    web::{
+        HttpAdapter, HttpParseError, HttpReceiver, HttpRequest, HttpResponder, HttpResponse,
+        HttpStatus,
+    },
 };
-use log::{debug, error, info};
-use std::{collections::HashMap, path::PathBuf};
-
-use super::conn::Connection;
+use log::{debug, error, info, warn};
+use std::{path::PathBuf, time::Instant};
 
-pub struct HttpServer {
-    socket: TcpSocket,
-    connections: HashMap<mio::Token, Connection>,
+pub struct HttpServer<T: Transport> {
+    socket: ListenSocket,
+    connections: Slab<T>,
     poll: mio::Poll,
     root: PathBuf,
-    nxid: usize,
+    dispatcher: Dispatcher,
+    timeouts: Timeouts,
+    limits: Limits,
+    timers: Timers,
+    heartbeat: Heartbeat,
+    config: T::Config,
 }
 
-impl Server<Self, ServerError> for HttpServer {
-    fn new(opts: Vec<CliOpt>) -> Self {
-        info!("initializing http server: {:?}", &opts);
+impl<T: Transport + 'static> Server<Self, ServerError> for HttpServer<T> {
+    fn new(config: &ServerConfig, listener: &Listener) -> Result<Self, ServerError> {
+        info!("initializing server: {}", listener);
+        debug!("server configuration: {:?}", config);
 
-        let socket_builder = SocketBuilder::<TcpSocket>::new(opts);
-        let thread_pool_builder = ThreadPoolBuilder::new(socket_builder.other());
-        let server_root_builder = ServerRootBuilder::new(thread_pool_builder.other());
+        let socket_builder = SocketBuilder::<ListenSocket>::new(config, listener);
+        let thread_pool_builder = ThreadPoolBuilder::new(config, listener);
+        let server_root_builder = ServerRootBuilder::new(config, listener);
+        let route_builder = RouteBuilder::new(config, listener);
+        let slab_builder = SlabBuilder::<T>::new(config, listener);
+        let timeouts_builder = TimeoutsBuilder::new(config, listener);
+        let limits_builder = LimitsBuilder::new(config, listener);
 
-        let mut socket = socket_builder.build().unwrap();
-        let root = server_root_builder.build().unwrap();
-
-        let poll = match mio::Poll::new() {
-            Ok(v) => v,
-            Err(e) => {
-                error!("error creating poll instance: {:?}", e);
-                panic!();
-            }
-        };
+        let mut socket = socket_builder.build()?;
+        let root = server_root_builder.build()?;
+        let router = route_builder.build()?;
+        let connections = slab_builder.build()?;
+        let timeouts = timeouts_builder.build()?;
+        let limits = limits_builder.build()?;
+        let config = T::config(config, listener)?;
+        let threads = thread_pool_builder.build()?;
 
-        match poll
-            .registry()
+        let poll = mio::Poll::new().map_err(ServerError::Poll)?;
+        poll.registry()
             .register(&mut socket, SERVER_SOCKET_TOKEN, mio::Interest::READABLE)
-        {
-            Ok(_) => debug!(
-                "registered readable interest for server socket: {:?}",
-                &socket
-            ),
-            Err(e) => {
-                error!(
-                    "error registering readable interest for server socket: {:?}: {:?}",
-                    &socket, e
-                );
-                panic!();
-            }
-        }
+            .map_err(ServerError::Poll)?;
+        debug!(
+            "registered readable interest for server socket: {:?}",
+            &socket
+        );
 
-        let connections = HashMap::new();
-        let nxid = 1;
+        let dispatcher =
+            Dispatcher::new(threads, router, poll.registry()).map_err(ServerError::Poll)?;
 
-        HttpServer {
+        Ok(HttpServer {
             socket,
             connections,
             poll,
             root,
-            nxid,
-        }
+            dispatcher,
+            timeouts,
+            limits,
+            timers: Timers::new(),
+            heartbeat: Heartbeat::register(),
+            config,
+        })
     }
     fn request(buf: &mut [u8]) -> Result<HttpRequest, ServerError> {
-        debug!("received {} byte buffer", buf.len());
         HttpAdapter::receive(buf).map_err(|e| ServerError::from(e))
     }
     fn response(req: &HttpRequest, root: &PathBuf) -> HttpResponse {
@@ -78,15 +86,81 @@ impl Server<Self, ServerError> for HttpServer {
     }
 }
 
-impl ConnectionHandler<ServerError> for HttpServer {
+impl<T: Transport + 'static> HttpServer<T> {
+    pub fn check(config: &ServerConfig, listener: &Listener) -> Vec<Problem> {
+        let mut problems: Vec<Problem> = [
+            (
+                &[][..],
+                SocketBuilder::<ListenSocket>::new(config, listener)
+                    .check()
+                    .map_err(ServerError::from),
+            ),
+            (
+                &[][..],
+                ThreadPoolBuilder::new(config, listener)
+                    .check()
+                    .map_err(ServerError::from),
+            ),
+            (
+                &["directory"][..],
+                ServerRootBuilder::new(config, listener)
+                    .check()
+                    .map_err(ServerError::from),
+            ),
+            (
+                &[][..],
+                RouteBuilder::new(config, listener)
+                    .check()
+                    .map_err(ServerError::from),
+            ),
+            (
+                &[][..],
+                SlabBuilder::<T>::new(config, listener)
+                    .check()
+                    .map_err(ServerError::from),
+            ),
+            (
+                &[][..],
+                TimeoutsBuilder::new(config, listener)
+                    .check()
+                    .map_err(ServerError::from),
+            ),
+            (
+                &[][..],
+                LimitsBuilder::new(config, listener)
+                    .check()
+                    .map_err(ServerError::from),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(options, result)| result.err().map(|e| (options, e)))
+        .collect();
+        problems.extend(T::check(config, listener));
+        problems
+    }
+}
+
+impl<T: Transport + 'static> ConnectionHandler<T, ServerError> for HttpServer<T> {
     fn listen(&mut self) {
         info!(
-            "listening for connections on socket {:?}",
-            self.socket.socket().local_addr().unwrap()
+            "listening for connections on socket {} with {} worker(s)",
+            self.socket,
+            self.dispatcher.size()
         );
         let mut events = mio::Events::with_capacity(SERVER_QUEUE_SIZE);
         loop {
-            match self.poll.poll(&mut events, None) {
+            self.heartbeat.beat();
+            if reactor::is_stopping() {
+                info!("closing listener on socket {}", self.socket);
+                return;
+            }
+            let now = Instant::now();
+            let reload = T::reload(&mut self.config, now).map(|v| v.saturating_duration_since(now));
+            let timeout = match (self.timers.next_timeout(now), reload) {
+                (Some(a), Some(b)) => a.min(b),
+                (a, b) => a.or(b).unwrap_or(HEARTBEAT),
+            };
+            match self.poll.poll(&mut events, Some(timeout.min(HEARTBEAT))) {
                 Ok(_) => {
                     for event in events.iter() {
                         match event.token() {
@@ -94,13 +168,23 @@ impl ConnectionHandler<ServerError> for HttpServer {
                                 Err(e) => error!("error accepting connection: {:?}", e),
                                 _ => {}
                             },
+                            SERVER_WAKER_TOKEN => {
+                                if let Err(e) = self.complete() {
+                                    error!("error completing requests: {:?}", e);
+                                }
+                            }
                             _ => match self.event(event) {
                                 Err(e) => error!("error handling request: {:?}", e),
                                 _ => {}
                             },
                         }
                     }
+                    if let Err(e) = self.expire() {
+                        error!("error expiring connections: {:?}", e);
+                    }
+                    self.connections.reclaim();
                 }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
                 Err(e) => {
                     error!("socket polling error: {:?}", e);
                 }
@@ -110,14 +194,34 @@ impl ConnectionHandler<ServerError> for HttpServer {
     fn accept(&mut self) -> Result<(), ServerError> {
         loop {
             match self.socket.accept() {
-                Ok((socket, _)) => {
-                    debug!("accepting new connection on socket: from: {:?}", &socket);
-                    let token = mio::Token(self.nxid);
-                    let mut connection = Connection::new(socket, token);
-                    connection.register(self.poll.registry());
-                    self.connections.insert(token, connection);
-
-                    self.nxid += 1;
+                Ok((socket, address)) => {
+                    if self.connections.is_full() {
+                        warn!(
+                            "connection limit of {} reached, rejecting connection from: {}",
+                            self.connections.capacity(),
+                            &address
+                        );
+                        T::refuse(socket);
+                        continue;
+                    }
+
+                    debug!("accepting new connection from: {}", &address);
+                    let registry = self.poll.registry();
+                    let config = &self.config;
+                    let token = self.connections.try_insert_with(|token, generation| {
+                        let mut connection = T::accept(socket, token, generation, config)?;
+                        connection.register(registry)?;
+                        Ok::<T, ConnectionError>(connection)
+                    });
+                    match token {
+                        Ok(Some(token)) => {
+                            if let Some(conn) = self.connections.get_mut(token) {
+                                schedule(&mut self.timers, &self.timeouts, conn);
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => error!("error setting up connection: {:?}", e),
+                    }
                 }
                 Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(()),
                 Err(e) => {
@@ -129,46 +233,154 @@ impl ConnectionHandler<ServerError> for HttpServer {
     }
     fn event(&mut self, event: &mio::event::Event) -> Result<(), ServerError> {
         let token = event.token();
-        if self.connections.contains_key(&token) {
-            match Self::handle(
+        if let Some(conn) = self.connections.get_mut(token) {
+            if let Err(e) = Self::handle(
                 event,
-                self.connections.get_mut(&token).unwrap(),
+                conn,
                 &self.poll,
                 &self.root,
+                &self.limits,
+                &self.dispatcher,
             ) {
-                Err(e) => error!("error handling connection: {:?}", e),
-                _ => {}
+                match e {
+                    ServerError::Connection(e) if e.is_handshake() => {
+                        info!("connection {:?}: {}", token, e)
+                    }
+                    e => error!("error handling connection: {:?}", e),
+                }
             }
 
-            if self.connections.get(&token).unwrap().is_closed() {
-                self.connections.remove(&token);
+            if conn.is_closed() {
+                self.connections.remove(token);
+            } else {
+                schedule(&mut self.timers, &self.timeouts, conn);
+            }
+        }
+        Ok(())
+    }
+    fn complete(&mut self) -> Result<(), ServerError> {
+        for completion in self.dispatcher.completions() {
+            let token = completion.token;
+            if self.connections.generation(token) != Some(completion.generation) {
+                debug!("dropping response for closed connection: {:?}", token);
+                continue;
+            }
+
+            if let Some(conn) = self.connections.get_mut(token) {
+                conn.complete(completion.seq, completion.buf, completion.close);
+                if let Err(e) = conn.write() {
+                    error!("error writing response: {:?}", e);
+                    conn.shutdown(std::net::Shutdown::Both, self.poll.registry());
+                } else if conn.is_finished() {
+                    conn.shutdown(std::net::Shutdown::Both, self.poll.registry());
+                } else if let Err(e) = conn.reregister(self.poll.registry()) {
+                    error!("error updating connection interest: {:?}", e);
+                    conn.shutdown(std::net::Shutdown::Both, self.poll.registry());
+                }
+
+                if conn.is_closed() {
+                    self.connections.remove(token);
+                } else {
+                    schedule(&mut self.timers, &self.timeouts, conn);
+                }
+            }
+        }
+        Ok(())
+    }
+    fn expire(&mut self) -> Result<(), ServerError> {
+        for deadline in self.timers.expired(Instant::now()) {
+            let generation = self.connections.generation(deadline.token);
+            let conn = match self.connections.get_mut(deadline.token) {
+                Some(v) if deadline.is_current(generation, v.deadline()) => v,
+                _ => continue,
+            };
+            debug!(
+                "connection {:?} timed out in phase {:?}",
+                deadline.token,
+                conn.phase()
+            );
+            conn.expire(self.poll.registry());
+            if conn.is_closed() {
+                self.connections.remove(deadline.token);
             }
         }
         Ok(())
     }
     fn handle(
         event: &mio::event::Event,
-        conn: &mut Connection,
+        conn: &mut T,
         poll: &mio::Poll,
         root: &PathBuf,
+        limits: &Limits,
+        dispatcher: &Dispatcher,
     ) -> Result<(), ServerError> {
         if event.is_readable() {
-            let mut buf = conn.read()?;
-            let request = Self::request(&mut buf)?;
-            let response = Self::response(&request, &root);
-            conn.write_b(response.to_buf())?;
+            if let Err(e) = conn.read() {
+                conn.shutdown(std::net::Shutdown::Both, poll.registry());
+                return Err(ServerError::from(e));
+            }
+            while !conn.is_closing() {
+                match conn.next_request(limits) {
+                    Ok(Some(buf)) => {
+                        debug!("received {} byte request", buf.len());
+                        let seq = conn.next_seq();
+                        let peer = conn.peer();
+                        if let Err(e) = dispatcher.dispatch::<Self>(
+                            conn.token(),
+                            conn.generation(),
+                            seq,
+                            buf,
+                            root,
+                            peer,
+                        ) {
+                            error!("error dispatching request: {:?}", e);
+                            conn.reject(HttpStatus::InternalServerError, poll.registry());
+                            return Ok(());
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(HttpParseError::HeaderSize(v)) => {
+                        warn!("request header of {} bytes exceeds the limit", v);
+                        conn.reject(HttpStatus::RequestHeaderFieldsTooLarge, poll.registry());
+                        return Ok(());
+                    }
+                    Err(HttpParseError::BodySize(v)) => {
+                        warn!("request body of {} bytes exceeds the limit", v);
+                        conn.reject(HttpStatus::RequestEntityTooLarge, poll.registry());
+                        return Ok(());
+                    }
+                    Err(HttpParseError::TransferEncoding(v)) => {
+                        warn!("unsupported request transfer encoding: {}", v);
+                        conn.reject(HttpStatus::NotImplemented, poll.registry());
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        error!("error framing request: {:?}", e);
+                        conn.reject(HttpStatus::BadRequest, poll.registry());
+                        return Ok(());
+                    }
+                }
+            }
         }
 
-        if event.is_writable() {
-            conn.write()?;
+        if let Err(e) = conn.write() {
+            conn.shutdown(std::net::Shutdown::Both, poll.registry());
+            return Err(ServerError::from(e));
         }
 
-        if conn.is_closing() {
+        if conn.is_finished() {
+            conn.shutdown(std::net::Shutdown::Both, poll.registry());
+        } else if let Err(e) = conn.reregister(poll.registry()) {
             conn.shutdown(std::net::Shutdown::Both, poll.registry());
-        } else {
-            conn.reregister(poll.registry());
+            return Err(ServerError::from(e));
         }
 
         Ok(())
     }
 }
+
+fn schedule<T: Transport>(timers: &mut Timers, timeouts: &Timeouts, conn: &mut T) {
+    if let Some(deadline) = conn.refresh(timeouts, Instant::now()) {
+        timers.schedule(deadline, conn.token(), conn.generation());
+    }
+}
@@ -0,0 +1,34 @@
+use crate::{
+    cli::{Builder, ServerConfig},
+    net::Listener,
+    srv::LimitError,
+};
+use log::trace;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Limits {
+    pub header: usize,
+    pub body: usize,
+}
+
+#[derive(Debug)]
+pub struct LimitsBuilder {
+    limits: Limits,
+}
+
+impl Builder<Self, Limits, LimitError> for LimitsBuilder {
+    fn new(config: &ServerConfig, _listener: &Listener) -> Self {
+        let limits_builder = LimitsBuilder {
+            limits: config.limits,
+        };
+
+        trace!("constructed limits builder: {:?}", &limits_builder);
+        limits_builder
+    }
+    fn build(&self) -> Result<Limits, LimitError> {
+        if self.limits.header == 0 {
+            return Err(LimitError::HeaderSize(0));
+        }
+        Ok(self.limits)
+    }
+}
@@ -1,66 +1,140 @@
 pub mod conn;
+pub mod dispatch;
 pub mod err;
 pub mod file;
 pub mod http;
-pub mod https;
+pub mod limit;
 pub mod log;
+pub mod reactor;
 pub mod root;
+pub mod route;
+pub mod slab;
+pub mod timer;
 
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Instant};
 
-pub use conn::SecureConnection;
-pub use err::{ConnectionError, ServerError, ServerRootError};
+pub use conn::{Connection, ConnectionState, SecureConnection};
+pub use dispatch::{Completion, Dispatcher};
+pub use err::{
+    ConnectionError, LimitError, RouteError, ServerError, ServerRootError, SlabError, TimerError,
+};
 pub use file::{File, FileReader};
 pub use http::HttpServer;
-pub use https::HttpsServer;
+pub use limit::{Limits, LimitsBuilder};
 pub use root::ServerRootBuilder;
+pub use route::{PathAction, PathRoute, Route, RouteBuilder, Router, VirtualHost};
+pub use slab::{Slab, SlabBuilder};
+pub use timer::{Phase, Timeouts, TimeoutsBuilder, Timers};
 
 use crate::{
-    cli,
-    web::{HttpRequest, HttpResponse},
+    cli, net,
+    web::{HttpParseError, HttpRequest, HttpResponse, HttpStatus, PeerCertificate},
 };
 
-use self::conn::Connection;
-
 pub const SERVER_SOCKET_TOKEN: mio::Token = mio::Token(0);
+pub const SERVER_WAKER_TOKEN: mio::Token = mio::Token(1);
+pub const SERVER_TOKEN_OFFSET: usize = 2;
 pub const SERVER_QUEUE_SIZE: usize = 256;
 
+pub type HttpsServer = HttpServer<SecureConnection>;
+pub type Problem = (&'static [&'static str], ServerError);
+
 pub trait Server<T, E>
 where
     T: Sized,
     E: Sized,
 {
-    fn new(opts: Vec<cli::CliOpt>) -> T;
+    fn new(config: &cli::ServerConfig, listener: &net::Listener) -> Result<T, E>;
     fn request(buf: &mut [u8]) -> Result<HttpRequest, E>;
     fn response(req: &HttpRequest, root: &PathBuf) -> HttpResponse;
 }
 
-pub trait ConnectionHandler<E>
+pub trait ConnectionHandler<T, E>
 where
+    T: Transport,
     E: Sized,
 {
     fn listen(&mut self);
     fn accept(&mut self) -> Result<(), E>;
     fn event(&mut self, event: &mio::event::Event) -> Result<(), E>;
+    fn complete(&mut self) -> Result<(), E>;
+    fn expire(&mut self) -> Result<(), E>;
     fn handle(
         event: &mio::event::Event,
-        conn: &mut Connection,
+        conn: &mut T,
         poll: &mio::Poll,
         root: &PathBuf,
+        limits: &Limits,
+        dispatcher: &Dispatcher,
     ) -> Result<(), E>;
 }
 
-pub trait SecureConnectionHandler<E>
-where
-    E: Sized,
-{
-    fn listen(&mut self);
-    fn accept(&mut self) -> Result<(), E>;
-    fn event(&mut self, event: &mio::event::Event) -> Result<(), E>;
-    fn handle(
-        event: &mio::event::Event,
-        conn: &mut SecureConnection,
-        poll: &mio::Poll,
-        root: &PathBuf,
-    ) -> Result<(), E>;
+pub trait Transport: Sized {
+    type Config;
+
+    fn config(
+        config: &cli::ServerConfig,
+        listener: &net::Listener,
+    ) -> Result<Self::Config, ServerError>;
+    fn check(config: &cli::ServerConfig, listener: &net::Listener) -> Vec<Problem>;
+    fn reload(config: &mut Self::Config, now: Instant) -> Option<Instant>;
+    fn accept(
+        socket: net::Stream,
+        token: mio::Token,
+        generation: usize,
+        config: &Self::Config,
+    ) -> Result<Self, ConnectionError>;
+    fn refuse(socket: net::Stream);
+    fn state(&self) -> &ConnectionState;
+    fn state_mut(&mut self) -> &mut ConnectionState;
+    fn interest(&self) -> mio::Interest;
+    fn current_phase(&self) -> Phase;
+    fn peer(&mut self) -> Option<PeerCertificate>;
+    fn is_finished(&self) -> bool;
+    fn token(&self) -> mio::Token {
+        self.state().token()
+    }
+    fn generation(&self) -> usize {
+        self.state().generation()
+    }
+    fn phase(&self) -> Phase {
+        self.state().phase()
+    }
+    fn deadline(&self) -> Option<Instant> {
+        self.state().deadline()
+    }
+    fn refresh(&mut self, timeouts: &Timeouts, now: Instant) -> Option<Instant> {
+        let phase = self.current_phase();
+        self.state_mut().refresh(phase, timeouts, now)
+    }
+    fn next_request(&mut self, limits: &Limits) -> Result<Option<Vec<u8>>, HttpParseError> {
+        self.state_mut().next_request(limits)
+    }
+    fn next_seq(&mut self) -> usize {
+        self.state_mut().next_seq()
+    }
+    fn complete(&mut self, seq: usize, buf: Vec<u8>, close: bool) {
+        self.state_mut().complete(seq, buf, close)
+    }
+    fn is_closing(&self) -> bool {
+        self.state().is_closing()
+    }
+    fn is_closed(&self) -> bool {
+        self.state().is_closed()
+    }
+    fn register(&mut self, registry: &mio::Registry) -> Result<(), ConnectionError> {
+        let interest = self.interest();
+        self.state_mut().register(registry, interest)
+    }
+    fn reregister(&mut self, registry: &mio::Registry) -> Result<(), ConnectionError> {
+        let interest = self.interest();
+        self.state_mut().reregister(registry, interest)
+    }
+    fn shutdown(&mut self, how: std::net::Shutdown, registry: &mio::Registry) {
+        self.state_mut().shutdown(how, registry)
+    }
+    fn read(&mut self) -> Result<usize, ConnectionError>;
+    fn write(&mut self) -> Result<usize, ConnectionError>;
+    fn reject(&mut self, status: HttpStatus, registry: &mio::Registry);
+    fn expire(&mut self, registry: &mio::Registry);
 }
@@ -0,0 +1,152 @@
+use log::{error, info};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const HEARTBEAT: Duration = Duration::from_secs(1);
+const TICK: Duration = Duration::from_millis(100);
+
+static STOPPING: AtomicBool = AtomicBool::new(false);
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+static HEARTBEATS: Mutex<Vec<Arc<AtomicU64>>> = Mutex::new(Vec::new());
+
+pub type Reactor = Arc<dyn Fn(&dyn Fn()) + Send + Sync>;
+
+#[derive(Debug)]
+pub struct Heartbeat(Arc<AtomicU64>);
+
+impl Heartbeat {
+    pub fn register() -> Heartbeat {
+        let heartbeat = Heartbeat(Arc::new(AtomicU64::new(elapsed())));
+        HEARTBEATS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Arc::clone(&heartbeat.0));
+        heartbeat
+    }
+    pub fn beat(&self) {
+        self.0.store(elapsed(), Ordering::Relaxed);
+    }
+}
+
+fn elapsed() -> u64 {
+    EPOCH.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+pub fn is_alive(max_age: Duration) -> bool {
+    let now = elapsed();
+    let heartbeats = HEARTBEATS.lock().unwrap_or_else(|e| e.into_inner());
+    !heartbeats.is_empty()
+        && heartbeats
+            .iter()
+            .all(|v| now.saturating_sub(v.load(Ordering::Relaxed)) <= max_age.as_millis() as u64)
+}
+
+pub fn stop() {
+    STOPPING.store(true, Ordering::SeqCst);
+}
+
+pub fn is_stopping() -> bool {
+    STOPPING.load(Ordering::SeqCst)
+}
+
+#[cfg(unix)]
+pub fn handle_signals() {
+    extern "C" fn on_signal(signal: libc::c_int) {
+        STOPPING.store(true, Ordering::SeqCst);
+        unsafe { libc::signal(signal, libc::SIG_DFL) };
+    }
+    for signal in [libc::SIGTERM, libc::SIGINT] {
+        let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        if unsafe { libc::signal(signal, handler) } == libc::SIG_ERR {
+            error!("unable to install handler for signal {}", signal);
+        }
+    }
+}
+
+pub fn run<F, S>(reactors: Vec<Reactor>, ready: F, stopping: S) -> bool
+where
+    F: FnOnce(),
+    S: FnOnce(),
+{
+    info!("starting {} reactor(s)", reactors.len());
+    let (sender, receiver) = mpsc::channel::<()>();
+    let mut handles = Vec::with_capacity(reactors.len());
+    for (id, reactor) in reactors.into_iter().enumerate() {
+        let sender = sender.clone();
+        match thread::Builder::new()
+            .name(format!("reactor-{}", id + 1))
+            .spawn(move || {
+                reactor(&|| {
+                    let _ = sender.send(());
+                })
+            }) {
+            Ok(v) => handles.push(v),
+            Err(e) => error!("error spawning reactor {}: {:?}", id + 1, e),
+        }
+    }
+    drop(sender);
+
+    let mut started = 0;
+    while started < handles.len() {
+        match receiver.recv_timeout(TICK) {
+            Ok(_) => started += 1,
+            Err(_) if handles.iter().any(|v| v.is_finished()) => break,
+            Err(_) => {}
+        }
+    }
+    if started < handles.len() || handles.is_empty() {
+        error!(
+            "{} of {} reactor(s) failed to start",
+            handles.len() - started,
+            handles.len()
+        );
+        stop();
+        stopping();
+        return false;
+    }
+    ready();
+
+    let mut stopping = Some(stopping);
+    while !handles.iter().all(|v| v.is_finished()) {
+        if is_stopping() {
+            if let Some(stopping) = stopping.take() {
+                info!("shutting down {} reactor(s)", handles.len());
+                stopping();
+            }
+        }
+        thread::sleep(TICK);
+    }
+    if let Some(stopping) = stopping.take() {
+        stopping();
+    }
+
+    let mut ok = true;
+    for handle in handles {
+        if handle.join().is_err() {
+            error!("reactor thread panicked");
+            ok = false;
+        }
+    }
+    ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heartbeat_keeps_the_reactors_alive() {
+        let heartbeat = Heartbeat::register();
+        assert!(is_alive(HEARTBEAT));
+        thread::sleep(Duration::from_millis(20));
+        assert!(!is_alive(Duration::from_millis(5)));
+        heartbeat.beat();
+        assert!(is_alive(Duration::from_millis(5)));
+    }
+}
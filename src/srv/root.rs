@@ -1,5 +1,6 @@
 use crate::{
-    cli::{Builder, CliOpt},
+    cli::{Builder, ServerConfig},
+    net::Listener,
     srv::ServerRootError,
 };
 use log::trace;
@@ -8,18 +9,13 @@ use std::path::PathBuf;
 #[derive(Debug)]
 pub struct ServerRootBuilder {
     root: PathBuf,
-    _other: Vec<CliOpt>,
 }
 
 impl Builder<Self, PathBuf, ServerRootError> for ServerRootBuilder {
-    fn new(opts: Vec<CliOpt>) -> Self {
-        let mut server_root_builder = Self::default();
-        for opt in opts {
-            match opt {
-                CliOpt::Directory(v) => server_root_builder.root = v.to_path_buf(),
-                cli_opt => server_root_builder.add_other(cli_opt.to_owned()),
-            }
-        }
+    fn new(config: &ServerConfig, _listener: &Listener) -> Self {
+        let server_root_builder = ServerRootBuilder {
+            root: config.directory.to_path_buf(),
+        };
 
         trace!(
             "constructed server root builder: {:?}",
@@ -28,21 +24,9 @@ impl Builder<Self, PathBuf, ServerRootError> for ServerRootBuilder {
         server_root_builder
     }
     fn build(&self) -> Result<PathBuf, ServerRootError> {
-        Ok(self.root.to_owned())
-    }
-    fn add_other(&mut self, o: CliOpt) {
-        self._other.push(o);
-    }
-    fn other(&self) -> Vec<CliOpt> {
-        self._other.to_vec()
-    }
-}
-
-impl Default for ServerRootBuilder {
-    fn default() -> Self {
-        ServerRootBuilder {
-            root: PathBuf::from("./"),
-            _other: Vec::new(),
+        if !self.root.is_dir() {
+            return Err(ServerRootError::NotFound(self.root.to_owned()));
         }
+        Ok(self.root.to_owned())
     }
 }
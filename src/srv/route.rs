@@ -0,0 +1,269 @@
+use crate::{
+    cli::{Builder, ServerConfig},
+    net::Listener,
+    srv::{FileReader, RouteError, Server, ServerError},
+    web::{
+        handle::{redirect, redirect::strip_port},
+        HttpBody, HttpRequest, HttpResponse, HttpStatus, HttpVersion,
+    },
+};
+use log::{debug, trace, warn};
+use std::path::PathBuf;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Route {
+    Serve,
+    Redirect(u16),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathAction {
+    Root(PathBuf),
+    Redirect(String, HttpStatus),
+    Status(HttpStatus),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathRoute {
+    pub path: PathBuf,
+    pub action: PathAction,
+    pub client_cert: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirtualHost {
+    pub names: Vec<String>,
+    pub root: PathBuf,
+    pub default: bool,
+    pub https_cert: Option<PathBuf>,
+    pub https_key: Option<PathBuf>,
+    pub https_ocsp: Option<PathBuf>,
+    pub headers: Vec<(String, String)>,
+    pub routes: Vec<PathRoute>,
+    pub error_pages: Vec<(HttpStatus, PathBuf)>,
+}
+
+impl VirtualHost {
+    pub fn exact(&self, host: &str) -> bool {
+        self.names
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(host))
+    }
+    pub fn wildcard(&self, host: &str) -> Option<usize> {
+        self.names
+            .iter()
+            .filter(|name| wildcard(name, host))
+            .map(|name| name.len())
+            .max()
+    }
+}
+
+pub fn wildcard(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix('*') {
+        Some(suffix) if suffix.starts_with('.') && host.len() > suffix.len() => host
+            .get(host.len() - suffix.len()..)
+            .is_some_and(|v| v.eq_ignore_ascii_case(suffix)),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Router {
+    pub route: Route,
+    pub paths: Vec<PathRoute>,
+    pub hosts: Vec<VirtualHost>,
+    pub headers: Vec<(String, String)>,
+    pub error_pages: Vec<(HttpStatus, PathBuf)>,
+}
+
+impl Router {
+    pub fn respond<S>(&self, request: &HttpRequest, root: &PathBuf) -> HttpResponse
+    where
+        S: Server<S, ServerError>,
+    {
+        let host = self.host(request);
+        let mut response = if matches!(request.version, HttpVersion::Http11)
+            && request.header.get("Host").is_none()
+        {
+            debug!("rejecting http/1.1 request without a host header");
+            HttpResponse::from(HttpStatus::BadRequest)
+        } else {
+            match self.route {
+                Route::Serve => self.serve::<S>(request, host, root),
+                Route::Redirect(port) => redirect(request, port),
+            }
+        };
+        self.error_page(host, &mut response);
+        let host_headers = host.into_iter().flat_map(|v| v.headers.iter());
+        for (key, value) in self.headers.iter().chain(host_headers) {
+            response.add_header(key.to_owned(), value.to_owned());
+        }
+        response
+    }
+    pub fn host(&self, request: &HttpRequest) -> Option<&VirtualHost> {
+        if let Some(host) = request.header.get("Host").map(|v| strip_port(v)) {
+            if let Some(v) = self.hosts.iter().find(|v| v.exact(host)) {
+                return Some(v);
+            }
+            if let Some(v) = self
+                .hosts
+                .iter()
+                .filter_map(|v| v.wildcard(host).map(|len| (len, v)))
+                .max_by_key(|(len, _)| *len)
+            {
+                return Some(v.1);
+            }
+        }
+        self.hosts.iter().find(|v| v.default)
+    }
+    pub fn path<'a>(
+        &'a self,
+        request: &HttpRequest,
+        host: Option<&'a VirtualHost>,
+    ) -> Option<&'a PathRoute> {
+        let host_routes = host.map(|v| &v.routes[..]).unwrap_or_default();
+        longest(host_routes, request).or_else(|| longest(&self.paths, request))
+    }
+    fn serve<S>(
+        &self,
+        request: &HttpRequest,
+        host: Option<&VirtualHost>,
+        root: &PathBuf,
+    ) -> HttpResponse
+    where
+        S: Server<S, ServerError>,
+    {
+        let root = match host {
+            Some(v) => &v.root,
+            None => root,
+        };
+        let route = self.path(request, host);
+        if route.is_some_and(|v| v.client_cert) && request.peer.is_none() {
+            debug!("rejecting {:?} without a client certificate", &request.uri);
+            return HttpResponse::from(HttpStatus::Forbidden);
+        }
+        match route.map(|v| &v.action) {
+            Some(PathAction::Root(v)) => S::response(request, v),
+            Some(PathAction::Redirect(location, status)) => {
+                debug!("redirecting {:?} to {}", &request.uri, location);
+                let mut response = HttpResponse::from(*status);
+                response.add_header(String::from("Location"), location.to_owned());
+                response
+            }
+            Some(PathAction::Status(status)) => HttpResponse::from(*status),
+            None => S::response(request, root),
+        }
+    }
+    fn error_page(&self, host: Option<&VirtualHost>, response: &mut HttpResponse) {
+        if (response.status as u16) < 400 {
+            return;
+        }
+        let find = |pages: &[(HttpStatus, PathBuf)]| {
+            pages
+                .iter()
+                .find(|(status, _)| *status == response.status)
+                .map(|(_, path)| path.to_path_buf())
+        };
+        let page = match host.and_then(|v| find(&v.error_pages)) {
+            Some(v) => v,
+            None => match find(&self.error_pages) {
+                Some(v) => v,
+                None => return,
+            },
+        };
+        match FileReader::new(&page).read() {
+            Ok(v) => {
+                response.content_type(v.mime().essence_str().to_owned());
+                response.content_length(v.size());
+                response.body = HttpBody::new(v.as_string());
+            }
+            Err(e) => warn!("failed to load error page {}: {:?}", page.display(), e),
+        }
+    }
+}
+
+fn longest<'a>(routes: &'a [PathRoute], request: &HttpRequest) -> Option<&'a PathRoute> {
+    routes
+        .iter()
+        .filter(|v| request.uri.starts_with(&v.path))
+        .max_by_key(|v| v.path.components().count())
+}
+
+#[derive(Debug)]
+pub struct RouteBuilder {
+    router: Router,
+}
+
+impl Builder<Self, Router, RouteError> for RouteBuilder {
+    fn new(config: &ServerConfig, listener: &Listener) -> Self {
+        let route_builder = RouteBuilder {
+            router: Router {
+                route: match config.redirect_port(listener) {
+                    Some(port) => Route::Redirect(port),
+                    None => Route::Serve,
+                },
+                paths: config.routes.to_vec(),
+                hosts: config.vhosts.to_vec(),
+                headers: config.headers.to_vec(),
+                error_pages: config.error_pages.to_vec(),
+            },
+        };
+
+        trace!("constructed route builder: {:?}", &route_builder);
+        route_builder
+    }
+    fn build(&self) -> Result<Router, RouteError> {
+        if let Route::Redirect(0) = self.router.route {
+            return Err(RouteError::RedirectPort(0));
+        }
+        let host_headers = self.router.hosts.iter().flat_map(|v| v.headers.iter());
+        for (key, value) in self.router.headers.iter().chain(host_headers) {
+            header(key, value)?;
+        }
+        let mut router = self.router.clone();
+        roots(&mut router.paths)?;
+        error_pages(&mut router.error_pages)?;
+        for host in router.hosts.iter_mut() {
+            host.root = root(&host.root)?;
+            roots(&mut host.routes)?;
+            error_pages(&mut host.error_pages)?;
+        }
+        Ok(router)
+    }
+}
+
+fn root(path: &PathBuf) -> Result<PathBuf, RouteError> {
+    match path.canonicalize() {
+        Ok(v) if v.is_dir() => Ok(v),
+        _ => Err(RouteError::Root(path.to_owned())),
+    }
+}
+
+fn roots(paths: &mut [PathRoute]) -> Result<(), RouteError> {
+    for path in paths.iter_mut() {
+        if let PathAction::Root(v) = &path.action {
+            path.action = PathAction::Root(root(v)?);
+        }
+    }
+    Ok(())
+}
+
+fn error_pages(pages: &mut [(HttpStatus, PathBuf)]) -> Result<(), RouteError> {
+    for (_, path) in pages.iter_mut() {
+        match path.canonicalize() {
+            Ok(v) if v.is_file() => *path = v,
+            _ => return Err(RouteError::ErrorPage(path.to_owned())),
+        }
+    }
+    Ok(())
+}
+
+fn header(key: &str, value: &str) -> Result<(), RouteError> {
+    if key.is_empty()
+        || !key.bytes().all(|v| v.is_ascii_graphic() && v != b':')
+        || value.bytes().any(|v| v == b'\r' || v == b'\n')
+    {
+        return Err(RouteError::Header(key.to_owned()));
+    }
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use crate::{
+    cli::{Builder, ServerConfig},
+    net::Listener,
+    srv::{SlabError, SERVER_TOKEN_OFFSET},
+};
+use log::trace;
+use std::marker::PhantomData;
+
+struct Entry<T> {
+    value: Option<T>,
+    generation: usize,
+}
+
+pub struct Slab<T> {
+    entries: Vec<Entry<T>>,
+    free: Vec<usize>,
+    released: Vec<usize>,
+    len: usize,
+    capacity: usize,
+}
+
+impl<T> Slab<T> {
+    pub fn with_capacity(capacity: usize) -> Slab<T> {
+        Slab {
+            entries: Vec::new(),
+            free: Vec::new(),
+            released: Vec::new(),
+            len: 0,
+            capacity,
+        }
+    }
+    pub fn try_insert_with<F, E>(&mut self, f: F) -> Result<Option<mio::Token>, E>
+    where
+        F: FnOnce(mio::Token, usize) -> Result<T, E>,
+    {
+        let index = match self.free.pop() {
+            Some(v) => v,
+            None if self.entries.len() < self.capacity => {
+                self.entries.push(Entry {
+                    value: None,
+                    generation: 0,
+                });
+                self.entries.len() - 1
+            }
+            None => return Ok(None),
+        };
+
+        let token = mio::Token(index + SERVER_TOKEN_OFFSET);
+        let entry = &mut self.entries[index];
+        match f(token, entry.generation) {
+            Ok(v) => {
+                entry.value = Some(v);
+                self.len += 1;
+                Ok(Some(token))
+            }
+            Err(e) => {
+                self.free.push(index);
+                Err(e)
+            }
+        }
+    }
+    pub fn get(&self, token: mio::Token) -> Option<&T> {
+        self.index(token)
+            .and_then(|index| self.entries[index].value.as_ref())
+    }
+    pub fn get_mut(&mut self, token: mio::Token) -> Option<&mut T> {
+        match self.index(token) {
+            Some(index) => self.entries[index].value.as_mut(),
+            None => None,
+        }
+    }
+    pub fn generation(&self, token: mio::Token) -> Option<usize> {
+        self.index(token)
+            .filter(|&index| self.entries[index].value.is_some())
+            .map(|index| self.entries[index].generation)
+    }
+    pub fn remove(&mut self, token: mio::Token) -> Option<T> {
+        let index = self.index(token)?;
+        let entry = &mut self.entries[index];
+        let value = entry.value.take()?;
+        entry.generation = entry.generation.wrapping_add(1);
+        self.released.push(index);
+        self.len -= 1;
+        Some(value)
+    }
+    pub fn reclaim(&mut self) {
+        if !self.released.is_empty() {
+            trace!("reclaiming {} connection slot(s)", self.released.len());
+            self.free.append(&mut self.released);
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn is_full(&self) -> bool {
+        self.len + self.released.len() >= self.capacity
+    }
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    fn index(&self, token: mio::Token) -> Option<usize> {
+        token
+            .0
+            .checked_sub(SERVER_TOKEN_OFFSET)
+            .filter(|&index| index < self.entries.len())
+    }
+}
+
+pub struct SlabBuilder<T> {
+    capacity: usize,
+    value_type: PhantomData<T>,
+}
+
+impl<T> Builder<Self, Slab<T>, SlabError> for SlabBuilder<T> {
+    fn new(config: &ServerConfig, _listener: &Listener) -> Self {
+        let slab_builder = SlabBuilder::<T> {
+            capacity: config.max_connections,
+            value_type: PhantomData,
+        };
+
+        trace!(
+            "constructed connection slab builder: capacity: {}",
+            slab_builder.capacity
+        );
+        slab_builder
+    }
+    fn build(&self) -> Result<Slab<T>, SlabError> {
+        if self.capacity == 0 {
+            return Err(SlabError::Capacity(self.capacity));
+        }
+        Ok(Slab::with_capacity(self.capacity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(slab: &mut Slab<usize>, value: usize) -> Option<mio::Token> {
+        slab.try_insert_with(|_, _| Ok::<usize, ()>(value)).unwrap()
+    }
+
+    #[test]
+    fn tokens_start_after_the_server_tokens() {
+        let mut slab = Slab::with_capacity(2);
+        let token = insert(&mut slab, 1).unwrap();
+        assert_eq!(token, mio::Token(SERVER_TOKEN_OFFSET));
+        assert_eq!(slab.get(token), Some(&1));
+        assert_eq!(slab.get(mio::Token(0)), None);
+        assert_eq!(slab.get(mio::Token(SERVER_TOKEN_OFFSET + 1)), None);
+    }
+
+    #[test]
+    fn generation_bumps_on_reuse() {
+        let mut slab = Slab::with_capacity(1);
+        let token = insert(&mut slab, 1).unwrap();
+        assert_eq!(slab.generation(token), Some(0));
+        assert_eq!(slab.remove(token), Some(1));
+        assert_eq!(slab.generation(token), None);
+        assert_eq!(slab.remove(token), None);
+        slab.reclaim();
+
+        let mut seen = None;
+        let reused = slab
+            .try_insert_with(|token, generation| {
+                seen = Some((token, generation));
+                Ok::<usize, ()>(2)
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(reused, token);
+        assert_eq!(seen, Some((token, 1)));
+        assert_eq!(slab.generation(token), Some(1));
+        assert_eq!(slab.get(token), Some(&2));
+    }
+
+    #[test]
+    fn removed_slots_wait_for_reclaim() {
+        let mut slab = Slab::with_capacity(3);
+        let first = insert(&mut slab, 1).unwrap();
+        slab.remove(first);
+        let second = insert(&mut slab, 2).unwrap();
+        assert_ne!(second, first);
+        slab.reclaim();
+        assert_eq!(insert(&mut slab, 3), Some(first));
+    }
+
+    #[test]
+    fn is_full_counts_unreclaimed_slots() {
+        let mut slab = Slab::with_capacity(2);
+        let first = insert(&mut slab, 1).unwrap();
+        insert(&mut slab, 2).unwrap();
+        assert!(slab.is_full());
+        assert_eq!(insert(&mut slab, 3), None);
+
+        slab.remove(first);
+        assert_eq!(slab.len(), 1);
+        assert!(slab.is_full());
+        assert_eq!(insert(&mut slab, 3), None);
+
+        slab.reclaim();
+        assert!(!slab.is_full());
+        assert_eq!(insert(&mut slab, 3), Some(first));
+        assert!(slab.is_full());
+    }
+
+    #[test]
+    fn failed_insert_frees_the_slot() {
+        let mut slab: Slab<usize> = Slab::with_capacity(1);
+        assert_eq!(slab.try_insert_with(|_, _| Err("refused")), Err("refused"));
+        assert!(slab.is_empty());
+        assert!(!slab.is_full());
+        assert!(insert(&mut slab, 1).is_some());
+    }
+}
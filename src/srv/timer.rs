@@ -0,0 +1,251 @@
+use crate::{
+    cli::{Builder, ServerConfig},
+    net::Listener,
+    srv::TimerError,
+};
+use log::trace;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Phase {
+    Header,
+    Body,
+    Processing,
+    Write,
+    Idle,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Timeouts {
+    pub header: Duration,
+    pub body: Duration,
+    pub processing: Duration,
+    pub write: Duration,
+    pub idle: Duration,
+}
+
+impl Timeouts {
+    pub fn get(&self, phase: Phase) -> Option<Duration> {
+        match phase {
+            Phase::Header => Some(self.header),
+            Phase::Body => Some(self.body),
+            Phase::Processing => Some(self.processing),
+            Phase::Write => Some(self.write),
+            Phase::Idle => Some(self.idle),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Deadline {
+    pub at: Instant,
+    pub token: mio::Token,
+    pub generation: usize,
+}
+
+impl Deadline {
+    pub fn is_current(&self, generation: Option<usize>, at: Option<Instant>) -> bool {
+        generation == Some(self.generation) && at == Some(self.at)
+    }
+}
+
+const COMPACT_MIN: usize = 64;
+
+#[derive(Debug, Default)]
+pub struct Timers {
+    heap: BinaryHeap<Reverse<Deadline>>,
+    current: HashMap<mio::Token, Deadline>,
+}
+
+impl Timers {
+    pub fn new() -> Timers {
+        Timers::default()
+    }
+    pub fn schedule(&mut self, at: Instant, token: mio::Token, generation: usize) {
+        trace!("scheduling deadline for connection {:?}: {:?}", token, at);
+        let deadline = Deadline {
+            at,
+            token,
+            generation,
+        };
+        self.current.insert(token, deadline);
+        self.heap.push(Reverse(deadline));
+        if self.heap.len() > COMPACT_MIN.max(2 * self.current.len()) {
+            self.compact();
+        }
+    }
+    pub fn next_timeout(&self, now: Instant) -> Option<Duration> {
+        self.heap
+            .peek()
+            .map(|Reverse(deadline)| deadline.at.saturating_duration_since(now))
+    }
+    pub fn expired(&mut self, now: Instant) -> Vec<Deadline> {
+        let mut expired = Vec::new();
+        while let Some(Reverse(deadline)) = self.heap.peek().copied() {
+            if self.is_stale(&deadline) {
+                self.heap.pop();
+                continue;
+            }
+            if deadline.at > now {
+                break;
+            }
+            expired.push(deadline);
+            self.current.remove(&deadline.token);
+            self.heap.pop();
+        }
+        expired
+    }
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+    fn is_stale(&self, deadline: &Deadline) -> bool {
+        self.current.get(&deadline.token) != Some(deadline)
+    }
+    fn compact(&mut self) {
+        trace!(
+            "compacting {} deadlines to {}",
+            self.heap.len(),
+            self.current.len()
+        );
+        self.heap = self.current.values().map(|v| Reverse(*v)).collect();
+    }
+}
+
+#[derive(Debug)]
+pub struct TimeoutsBuilder {
+    timeouts: Timeouts,
+}
+
+impl Builder<Self, Timeouts, TimerError> for TimeoutsBuilder {
+    fn new(config: &ServerConfig, _listener: &Listener) -> Self {
+        let timeouts_builder = TimeoutsBuilder {
+            timeouts: config.timeouts,
+        };
+
+        trace!("constructed timeouts builder: {:?}", &timeouts_builder);
+        timeouts_builder
+    }
+    fn build(&self) -> Result<Timeouts, TimerError> {
+        for phase in [
+            Phase::Header,
+            Phase::Body,
+            Phase::Processing,
+            Phase::Write,
+            Phase::Idle,
+        ] {
+            if let Some(v) = self.timeouts.get(phase) {
+                if v.is_zero() {
+                    return Err(TimerError::Zero(phase));
+                }
+            }
+        }
+        Ok(self.timeouts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expired_in_deadline_order() {
+        let now = Instant::now();
+        let mut timers = Timers::new();
+        for (secs, token) in [(3, 3), (1, 1), (2, 2)] {
+            timers.schedule(now + Duration::from_secs(secs), mio::Token(token), 0);
+        }
+        assert_eq!(timers.next_timeout(now), Some(Duration::from_secs(1)));
+        let tokens = |v: Vec<Deadline>| v.iter().map(|v| v.token.0).collect::<Vec<usize>>();
+        assert_eq!(tokens(timers.expired(now)), Vec::<usize>::new());
+        assert_eq!(
+            tokens(timers.expired(now + Duration::from_millis(2500))),
+            vec![1, 2]
+        );
+        assert_eq!(timers.next_timeout(now), Some(Duration::from_secs(3)));
+        assert_eq!(
+            tokens(timers.expired(now + Duration::from_secs(5))),
+            vec![3]
+        );
+        assert_eq!(timers.next_timeout(now), None);
+    }
+
+    #[test]
+    fn superseded_deadlines_are_skipped() {
+        let now = Instant::now();
+        let mut timers = Timers::new();
+        timers.schedule(now + Duration::from_secs(1), mio::Token(1), 0);
+        timers.schedule(now + Duration::from_secs(3), mio::Token(1), 0);
+        timers.schedule(now + Duration::from_secs(2), mio::Token(2), 0);
+        let expired = timers.expired(now + Duration::from_secs(2));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].token, mio::Token(2));
+        assert_eq!(timers.next_timeout(now), Some(Duration::from_secs(3)));
+        assert_eq!(
+            timers.expired(now + Duration::from_secs(3))[0].token,
+            mio::Token(1)
+        );
+        assert!(timers.is_empty());
+    }
+
+    #[test]
+    fn refreshing_one_connection_does_not_grow_the_heap() {
+        let now = Instant::now();
+        let mut timers = Timers::new();
+        for i in 0..10_000 {
+            timers.schedule(now + Duration::from_millis(i), mio::Token(1), 0);
+        }
+        assert!(timers.len() <= COMPACT_MIN);
+        let expired = timers.expired(now + Duration::from_secs(60));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].at, now + Duration::from_millis(9_999));
+    }
+
+    #[test]
+    fn next_timeout_saturates() {
+        let now = Instant::now();
+        let mut timers = Timers::new();
+        timers.schedule(now, mio::Token(2), 0);
+        assert_eq!(
+            timers.next_timeout(now + Duration::from_secs(1)),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn stale_deadlines_are_not_current() {
+        let now = Instant::now();
+        let deadline = Deadline {
+            at: now,
+            token: mio::Token(2),
+            generation: 4,
+        };
+        assert!(deadline.is_current(Some(4), Some(now)));
+        assert!(!deadline.is_current(Some(5), Some(now)));
+        assert!(!deadline.is_current(None, Some(now)));
+        assert!(!deadline.is_current(Some(4), Some(now + Duration::from_secs(1))));
+        assert!(!deadline.is_current(Some(4), None));
+    }
+
+    #[test]
+    fn timeouts_by_phase() {
+        let timeouts = Timeouts {
+            header: Duration::from_secs(1),
+            body: Duration::from_secs(2),
+            processing: Duration::from_secs(5),
+            write: Duration::from_secs(3),
+            idle: Duration::from_secs(4),
+        };
+        assert_eq!(timeouts.get(Phase::Body), Some(Duration::from_secs(2)));
+        assert_eq!(
+            timeouts.get(Phase::Processing),
+            Some(Duration::from_secs(5))
+        );
+    }
+}
@@ -1,4 +1,5 @@
 #[derive(Debug)]
 pub enum ThreadPoolError {
     General(String),
+    Disconnected,
 }
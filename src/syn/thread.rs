@@ -1,8 +1,9 @@
 use crate::{
-    cli::{default, Builder, CliOpt},
+    cli::{default, Builder, ServerConfig},
+    net::Listener,
     syn::{Message, ThreadPoolError, Tx, Worker},
 };
-use log::{debug, trace};
+use log::{debug, error, trace};
 use std::sync::{mpsc, Arc, Mutex};
 
 pub struct ThreadPool {
@@ -12,8 +13,6 @@ pub struct ThreadPool {
 
 impl ThreadPool {
     pub fn new(size: usize) -> ThreadPool {
-        assert!(size > 0);
-
         let (sender, receiver) = mpsc::channel::<Message>();
         let receiver = Arc::new(Mutex::new(receiver));
         let mut workers = Vec::with_capacity(size);
@@ -23,13 +22,21 @@ impl ThreadPool {
 
         ThreadPool { workers, sender }
     }
-    pub fn execute<F>(&self, f: F)
+    pub fn execute<F>(&self, f: F) -> Result<(), ThreadPoolError>
     where
         F: FnOnce() + Send + 'static,
     {
+        if self.workers.is_empty() {
+            trace!("executing job inline");
+            f();
+            return Ok(());
+        }
+
         debug!("sending execute message to thread pool");
         let job = Box::new(f);
-        self.sender.send(Message::Job(job)).unwrap();
+        self.sender
+            .send(Message::Job(job))
+            .map_err(|_| ThreadPoolError::Disconnected)
     }
     pub fn size(&self) -> usize {
         self.workers.len()
@@ -41,13 +48,17 @@ impl Drop for ThreadPool {
         debug!("sending terminate message to all workers");
 
         for _ in &self.workers {
-            self.sender.send(Message::Terminate).unwrap();
+            if self.sender.send(Message::Terminate).is_err() {
+                break;
+            }
         }
 
         for worker in &mut self.workers {
             debug!("shutting down worker {}", worker.id);
             if let Some(thread) = worker.thread.take() {
-                thread.join().unwrap();
+                if thread.join().is_err() {
+                    error!("worker {} panicked", worker.id);
+                }
             }
         }
     }
@@ -56,18 +67,15 @@ impl Drop for ThreadPool {
 #[derive(Debug)]
 pub struct ThreadPoolBuilder {
     pool_size: usize,
-    _other: Vec<CliOpt>,
+    inline: bool,
 }
 
 impl Builder<Self, ThreadPool, ThreadPoolError> for ThreadPoolBuilder {
-    fn new(opts: Vec<CliOpt>) -> Self {
-        let mut thread_pool_builder = Self::default();
-        for opt in opts {
-            match opt {
-                CliOpt::Threads(v) => thread_pool_builder.pool_size = v,
-                cli_opt => thread_pool_builder.add_other(cli_opt.to_owned()),
-            }
-        }
+    fn new(config: &ServerConfig, listener: &Listener) -> Self {
+        let thread_pool_builder = ThreadPoolBuilder {
+            pool_size: config.threads,
+            inline: config.reactors_for(listener),
+        };
 
         trace!(
             "constructed thread pool builder: {:?}",
@@ -76,21 +84,36 @@ impl Builder<Self, ThreadPool, ThreadPoolError> for ThreadPoolBuilder {
         thread_pool_builder
     }
     fn build(&self) -> Result<ThreadPool, ThreadPoolError> {
+        self.check()?;
+        if self.inline {
+            return Ok(ThreadPool::new(0));
+        }
         Ok(ThreadPool::new(self.pool_size))
     }
-    fn add_other(&mut self, o: CliOpt) {
-        self._other.push(o);
-    }
-    fn other(&self) -> Vec<CliOpt> {
-        self._other.to_vec()
+    fn check(&self) -> Result<(), ThreadPoolError> {
+        if self.pool_size == 0 || self.pool_size > default::threads_max() {
+            return Err(ThreadPoolError::General(format!(
+                "thread count must be between 1 and {}, got: {}",
+                default::threads_max(),
+                self.pool_size
+            )));
+        }
+        Ok(())
     }
 }
 
-impl Default for ThreadPoolBuilder {
-    fn default() -> Self {
-        ThreadPoolBuilder {
-            pool_size: default::threads(),
-            _other: Vec::new(),
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn panicking_job_keeps_the_worker() {
+        let pool = ThreadPool::new(1);
+        let (sender, receiver) = mpsc::channel();
+        pool.execute(|| panic!("job panicked")).unwrap();
+        pool.execute(move || sender.send(()).unwrap()).unwrap();
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+        drop(pool);
     }
 }
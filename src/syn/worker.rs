@@ -1,6 +1,7 @@
 use crate::syn::Message;
-use log::debug;
+use log::{debug, error};
 use std::{
+    panic::{self, AssertUnwindSafe},
     sync::{mpsc, Arc, Mutex},
     thread,
 };
@@ -17,21 +18,23 @@ pub struct Worker {
 impl Worker {
     pub fn new(id: usize, receiver: Rx) -> Worker {
         let thread = thread::spawn(move || loop {
-            let message = receiver
-                .lock()
-                .expect("failed to acquire thread message lock!")
-                .recv()
-                .unwrap();
+            let message = receiver.lock().unwrap_or_else(|e| e.into_inner()).recv();
 
             match message {
-                Message::Job(job) => {
+                Ok(Message::Job(job)) => {
                     debug!("worker {} got a job", id);
-                    job();
+                    if panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                        error!("worker {} job panicked", id);
+                    }
                 }
-                Message::Terminate => {
+                Ok(Message::Terminate) => {
                     debug!("worker {} got a terminate message", id);
                     break;
                 }
+                Err(_) => {
+                    debug!("worker {} lost its thread pool", id);
+                    break;
+                }
             }
         });
         Worker {
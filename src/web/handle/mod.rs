@@ -1,3 +1,5 @@
 pub mod get;
+pub mod redirect;
 
 pub use get::get;
+pub use redirect::redirect;
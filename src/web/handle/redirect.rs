@@ -0,0 +1,38 @@
+use crate::web::{HttpRequest, HttpResponse, HttpStatus};
+use log::{debug, trace};
+
+pub fn redirect(request: &HttpRequest, port: u16) -> HttpResponse {
+    let host = match request.header.get("Host") {
+        Some(v) => strip_port(v),
+        None => {
+            debug!("cannot redirect request without a host header");
+            return HttpResponse::from(HttpStatus::BadRequest);
+        }
+    };
+
+    let location = match port {
+        443 => format!("https://{}{}", host, request.uri.display()),
+        _ => format!("https://{}:{}{}", host, port, request.uri.display()),
+    };
+    debug!("redirecting {:?} to {}", &request.uri, &location);
+
+    let mut response = HttpResponse::from(HttpStatus::MovedPermanently);
+    response.add_header(String::from("Location"), location);
+
+    trace!("built response: {:?}", &response);
+    response
+}
+
+pub fn strip_port(host: &str) -> &str {
+    if host.starts_with('[') {
+        match host.find(']') {
+            Some(i) => &host[..i + 1],
+            None => host,
+        }
+    } else {
+        match host.rsplit_once(':') {
+            Some((v, _)) => v,
+            None => host,
+        }
+    }
+}
@@ -37,6 +37,7 @@ impl HttpReceiver<HttpRequest, HttpParseError> for HttpAdapter {
             version: req_line.version,
             header,
             body,
+            peer: None,
         })
     }
 }
@@ -32,4 +32,7 @@ pub enum HttpParseError {
     HttpMethod(String),
     HttpHeader(String),
     HttpRequestLine(String),
+    HeaderSize(usize),
+    BodySize(usize),
+    TransferEncoding(String),
 }
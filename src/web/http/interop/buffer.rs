@@ -32,6 +32,53 @@ pub fn tokenize_s(str: &String) -> TokenIter {
         .into_iter()
 }
 
+pub fn header_len(buf: &[u8]) -> Option<usize> {
+    let delim = [delim::CRLF, delim::CRLF].concat();
+    buf.windows(delim.len())
+        .position(|v| v == delim.as_bytes())
+        .map(|v| v + delim.len())
+}
+
+pub fn content_length(header: &[u8]) -> Result<usize, HttpParseError> {
+    let header = match std::str::from_utf8(header) {
+        Ok(v) => v,
+        Err(e) => return Err(HttpParseError::Buffer(format!("{:?}", e))),
+    };
+
+    let mut content_length = None;
+    for line in header.split(delim::CRLF) {
+        if let Some((key, value)) = line.split_once(':') {
+            let (key, value) = (key.trim(), value.trim());
+            if key.eq_ignore_ascii_case("Transfer-Encoding") {
+                return Err(HttpParseError::TransferEncoding(value.to_owned()));
+            }
+            if !key.eq_ignore_ascii_case("Content-Length") {
+                continue;
+            }
+            let length = match value.bytes().all(|v| v.is_ascii_digit()) {
+                true => value.parse::<usize>().ok(),
+                false => None,
+            };
+            content_length = match (length, content_length) {
+                (Some(v), None) => Some(v),
+                (Some(v), Some(w)) if v == w => Some(v),
+                (Some(_), Some(_)) => {
+                    return Err(HttpParseError::HttpHeader(String::from(
+                        "conflicting Content-Length headers",
+                    )))
+                }
+                (None, _) => {
+                    return Err(HttpParseError::HttpHeader(format!(
+                        "invalid Content-Length: {}",
+                        value
+                    )))
+                }
+            };
+        }
+    }
+    Ok(content_length.unwrap_or(0))
+}
+
 impl ToBuffer for HttpBody {
     fn to_buf(&self) -> Vec<u8> {
         self.tokens.join("").into_bytes()
@@ -119,6 +166,7 @@ impl ToBuffer for HttpStatus {
 impl ToBuffer for HttpVersion {
     fn to_buf(&self) -> Vec<u8> {
         match self {
+            Self::Http10 => b"HTTP/1.0".to_vec(),
             Self::Http11 => b"HTTP/1.1".to_vec(),
             Self::Http20 => b"HTTP/2.0".to_vec(),
             Self::Http30 => b"HTTP/3.0".to_vec(),
@@ -1,7 +1,7 @@
 pub mod buffer;
 pub mod parse;
 
-pub use buffer::{stringify, tokenize_s, TokenIter};
+pub use buffer::{content_length, header_len, stringify, tokenize_s, TokenIter};
 
 use crate::web::HttpParseError;
 
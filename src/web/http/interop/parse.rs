@@ -64,7 +64,7 @@ impl HttpParser<&String, Self> for HttpMethod {
 impl HttpParser<&String, Self> for HttpVersion {
     fn parse(v: &String) -> Result<Self, HttpParseError> {
         match &v.to_uppercase()[..] {
-            "HTTP/1.0" => Ok(HttpVersion::Http11),
+            "HTTP/1.0" => Ok(HttpVersion::Http10),
             "HTTP/1.1" => Ok(HttpVersion::Http11),
             "HTTP/2.0" => Ok(HttpVersion::Http20),
             _ => Err(HttpParseError::HttpMethod(format!("{}", v))),
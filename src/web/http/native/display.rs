@@ -45,6 +45,9 @@ impl Display for HttpStatus {
                 write!(f, "{} Requested Range Not Satisfiable", (*self as usize))
             }
             Self::ExpectationFailed => write!(f, "{} Expectation Failed", (*self as usize)),
+            Self::RequestHeaderFieldsTooLarge => {
+                write!(f, "{} Request Header Fields Too Large", (*self as usize))
+            }
             Self::InternalServerError => write!(f, "{} Internal Server Error", (*self as usize)),
             Self::NotImplemented => write!(f, "{} Not Implemented", (*self as usize)),
             Self::BadGateway => write!(f, "{} Bad Gateway", (*self as usize)),
@@ -1,6 +1,7 @@
 pub mod default;
 pub mod display;
 
+use crate::web::PeerCertificate;
 use std::{collections::HashMap, path::PathBuf};
 
 #[derive(Debug, Copy, Clone)]
@@ -18,12 +19,13 @@ pub enum HttpMethod {
 
 #[derive(Debug, Copy, Clone)]
 pub enum HttpVersion {
+    Http10,
     Http11,
     Http20,
     Http30,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum HttpStatus {
     Continue = 100,
     SwitchingProtocols = 101,
@@ -58,6 +60,7 @@ pub enum HttpStatus {
     UnsupportedMediaType = 415,
     RequestedRangeNotSatisfiable = 416,
     ExpectationFailed = 417,
+    RequestHeaderFieldsTooLarge = 431,
     InternalServerError = 500,
     NotImplemented = 501,
     BadGateway = 502,
@@ -66,6 +69,56 @@ pub enum HttpStatus {
     HTTPVersionNotSupported = 505,
 }
 
+impl TryFrom<u16> for HttpStatus {
+    type Error = u16;
+
+    fn try_from(v: u16) -> Result<Self, Self::Error> {
+        match v {
+            100 => Ok(Self::Continue),
+            101 => Ok(Self::SwitchingProtocols),
+            200 => Ok(Self::OK),
+            201 => Ok(Self::Created),
+            202 => Ok(Self::Accepted),
+            203 => Ok(Self::NonAuthoritativeInformation),
+            204 => Ok(Self::NoContent),
+            205 => Ok(Self::ResetContent),
+            206 => Ok(Self::PartialContent),
+            300 => Ok(Self::MultipleChoices),
+            301 => Ok(Self::MovedPermanently),
+            302 => Ok(Self::Found),
+            303 => Ok(Self::SeeOther),
+            304 => Ok(Self::NotModified),
+            305 => Ok(Self::UseProxy),
+            307 => Ok(Self::TemporaryRedirect),
+            400 => Ok(Self::BadRequest),
+            401 => Ok(Self::Unauthorized),
+            403 => Ok(Self::Forbidden),
+            404 => Ok(Self::NotFound),
+            405 => Ok(Self::MethodNotAllowed),
+            406 => Ok(Self::NotAcceptable),
+            407 => Ok(Self::ProxyAuthenticationRequired),
+            408 => Ok(Self::RequestTimeout),
+            409 => Ok(Self::Conflict),
+            410 => Ok(Self::Gone),
+            411 => Ok(Self::LengthRequired),
+            412 => Ok(Self::PreconditionFailed),
+            413 => Ok(Self::RequestEntityTooLarge),
+            414 => Ok(Self::RequestURITooLong),
+            415 => Ok(Self::UnsupportedMediaType),
+            416 => Ok(Self::RequestedRangeNotSatisfiable),
+            417 => Ok(Self::ExpectationFailed),
+            431 => Ok(Self::RequestHeaderFieldsTooLarge),
+            500 => Ok(Self::InternalServerError),
+            501 => Ok(Self::NotImplemented),
+            502 => Ok(Self::BadGateway),
+            503 => Ok(Self::ServiceUnavailable),
+            504 => Ok(Self::GatewayTimeout),
+            505 => Ok(Self::HTTPVersionNotSupported),
+            v => Err(v),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct HttpLine {
     pub method: HttpMethod,
@@ -90,6 +143,15 @@ pub struct HttpHeader {
     pub headers: HashMap<String, String>,
 }
 
+impl HttpHeader {
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+}
+
 #[derive(Debug)]
 pub struct HttpBody {
     pub tokens: Vec<String>,
@@ -108,6 +170,17 @@ pub struct HttpRequest {
     pub version: HttpVersion,
     pub header: HttpHeader,
     pub body: HttpBody,
+    pub peer: Option<PeerCertificate>,
+}
+
+impl HttpRequest {
+    pub fn is_close(&self) -> bool {
+        match self.header.get("Connection") {
+            Some(v) if v.eq_ignore_ascii_case("close") => true,
+            Some(v) if v.eq_ignore_ascii_case("keep-alive") => false,
+            _ => matches!(self.version, HttpVersion::Http10),
+        }
+    }
 }
 
 #[derive(Debug)]
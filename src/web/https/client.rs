@@ -0,0 +1,135 @@
+use crate::web::https::x509::{Crl, PeerCertificate};
+use log::{info, warn};
+use rustls::{
+    server::{ClientCertVerified, ClientCertVerifier},
+    Certificate, DistinguishedNames,
+};
+use std::{fmt, str::FromStr, sync::Arc, time::SystemTime};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClientAuth {
+    None,
+    Optional,
+    Required,
+}
+
+impl FromStr for ClientAuth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "optional" => Ok(Self::Optional),
+            "required" => Ok(Self::Required),
+            _ => Err(String::from("expected one of none, optional or required")),
+        }
+    }
+}
+
+impl fmt::Display for ClientAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Optional => write!(f, "optional"),
+            Self::Required => write!(f, "required"),
+        }
+    }
+}
+
+pub struct CrlVerifier {
+    inner: Arc<dyn ClientCertVerifier>,
+    crls: Vec<Crl>,
+}
+
+impl CrlVerifier {
+    pub fn new(inner: Arc<dyn ClientCertVerifier>, crls: Vec<Crl>) -> CrlVerifier {
+        CrlVerifier { inner, crls }
+    }
+}
+
+impl ClientCertVerifier for CrlVerifier {
+    fn offer_client_auth(&self) -> bool {
+        self.inner.offer_client_auth()
+    }
+    fn client_auth_mandatory(&self) -> Option<bool> {
+        self.inner.client_auth_mandatory()
+    }
+    fn client_auth_root_subjects(&self) -> Option<DistinguishedNames> {
+        self.inner.client_auth_root_subjects()
+    }
+    fn verify_client_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        now: SystemTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        let verified = self
+            .inner
+            .verify_client_cert(end_entity, intermediates, now)?;
+        if let Some(e) = self.crls.iter().find_map(|crl| crl.check(now).err()) {
+            warn!(
+                "rejecting client certificate, the client CRL is unusable: {}",
+                e
+            );
+            return Err(rustls::Error::InvalidCertificateData(e));
+        }
+        for cert in std::iter::once(end_entity).chain(intermediates) {
+            if self.crls.iter().any(|crl| crl.is_revoked(&cert.0)) {
+                if let Some(v) = PeerCertificate::parse(&cert.0) {
+                    info!(
+                        "rejecting revoked client certificate {} (serial {})",
+                        v.subject, v.serial
+                    );
+                }
+                return Err(rustls::Error::InvalidCertificateData(String::from(
+                    "certificate revoked",
+                )));
+            }
+        }
+        Ok(verified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::https::testdata::{cert, read};
+    use rustls::server::AllowAnyAuthenticatedClient;
+
+    fn verifier() -> (CrlVerifier, Crl) {
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(&Certificate(cert("ca.pem"))).unwrap();
+        let crl = Crl::parse(&read("crl.der")).unwrap();
+        let inner = AllowAnyAuthenticatedClient::new(roots);
+        (CrlVerifier::new(inner, vec![crl.clone()]), crl)
+    }
+
+    #[test]
+    fn revoked_certificates_are_rejected() {
+        let (verifier, crl) = verifier();
+        let now = crl.this_update;
+        assert!(verifier
+            .verify_client_cert(&Certificate(cert("leaf.pem")), &[], now)
+            .is_ok());
+        match verifier.verify_client_cert(&Certificate(cert("revoked.pem")), &[], now) {
+            Err(rustls::Error::InvalidCertificateData(e)) => assert_eq!(e, "certificate revoked"),
+            v => panic!("expected a revoked certificate error, got {:?}", v.is_ok()),
+        }
+    }
+
+    #[test]
+    fn crl_expiring_after_startup_rejects_client_certificates() {
+        let (verifier, crl) = verifier();
+        let next_update = crl.next_update.unwrap();
+        let leaf = Certificate(cert("leaf.pem"));
+        assert!(verifier
+            .verify_client_cert(&leaf, &[], next_update - std::time::Duration::from_secs(1))
+            .is_ok());
+        match verifier.verify_client_cert(&leaf, &[], next_update) {
+            Err(rustls::Error::InvalidCertificateData(e)) => {
+                assert!(e.contains("expired"), "{}", e)
+            }
+            v => panic!("expected an expired CRL error, got {:?}", v.is_ok()),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use std::time::SystemTime;
+
+pub fn tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *input.first()?;
+    let first = *input.get(1)? as usize;
+    let (len, start) = if first < 0x80 {
+        (first, 2)
+    } else {
+        let n = first & 0x7f;
+        if n == 0 || n > 4 {
+            return None;
+        }
+        let len = input
+            .get(2..2 + n)?
+            .iter()
+            .fold(0, |len, v| len << 8 | *v as usize);
+        (len, 2 + n)
+    };
+    let value = input.get(start..start.checked_add(len)?)?;
+    Some((tag, value, &input[start + len..]))
+}
+
+pub fn encode(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    if value.len() < 0x80 {
+        out.push(value.len() as u8);
+    } else {
+        let len = value.len().to_be_bytes();
+        let len: Vec<u8> = len.iter().copied().skip_while(|v| *v == 0).collect();
+        out.push(0x80 | len.len() as u8);
+        out.extend(len);
+    }
+    out.extend_from_slice(value);
+    out
+}
+
+pub type Element<'a> = (u8, &'a [u8], &'a [u8]);
+
+pub fn elements(mut input: &[u8]) -> Option<Vec<Element<'_>>> {
+    let mut out = Vec::new();
+    while !input.is_empty() {
+        let (tag, value, rest) = tlv(input)?;
+        out.push((tag, value, &input[..input.len() - rest.len()]));
+        input = rest;
+    }
+    Some(out)
+}
+
+pub fn oid(der: &[u8]) -> String {
+    let mut parts = Vec::new();
+    let mut value: u64 = 0;
+    for byte in der {
+        value = value << 7 | (*byte & 0x7f) as u64;
+        if *byte & 0x80 != 0 {
+            continue;
+        }
+        if parts.is_empty() {
+            let first = (value / 40).min(2);
+            parts.push(first);
+            parts.push(value - first * 40);
+        } else {
+            parts.push(value);
+        }
+        value = 0;
+    }
+    parts
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+pub fn time(tag: u8, value: &[u8]) -> Option<SystemTime> {
+    let value = std::str::from_utf8(value).ok()?.strip_suffix('Z')?;
+    let value = value.split('.').next()?;
+    let time = match tag {
+        0x17 => {
+            let year: i32 = value.get(..2)?.parse().ok()?;
+            let century = if year < 50 { "20" } else { "19" };
+            NaiveDateTime::parse_from_str(&format!("{}{}", century, value), "%Y%m%d%H%M%S")
+        }
+        0x18 => NaiveDateTime::parse_from_str(value, "%Y%m%d%H%M%S"),
+        _ => return None,
+    }
+    .ok()?;
+    Some(SystemTime::from(Utc.from_utc_datetime(&time)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn short_and_long_lengths() {
+        for len in [0, 1, 127, 128, 255, 256, 65535, 65536] {
+            let value = vec![0xab; len];
+            let der = [encode(0x04, &value), vec![0x05, 0x00]].concat();
+            let (tag, parsed, rest) = tlv(&der).unwrap();
+            assert_eq!((tag, parsed), (0x04, value.as_slice()), "length {}", len);
+            assert_eq!(rest, &[0x05, 0x00]);
+        }
+        assert_eq!(encode(0x04, &[0; 128])[..3], [0x04, 0x81, 0x80]);
+        assert_eq!(encode(0x04, &[0; 256])[..4], [0x04, 0x82, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn invalid_lengths_are_rejected() {
+        assert_eq!(tlv(&[]), None);
+        assert_eq!(tlv(&[0x30]), None);
+        assert_eq!(tlv(&[0x30, 0x02, 0x00]), None);
+        assert_eq!(tlv(&[0x30, 0x80]), None);
+        assert_eq!(tlv(&[0x30, 0x85, 0, 0, 0, 0, 1, 0]), None);
+        assert_eq!(tlv(&[0x30, 0x82, 0x01]), None);
+        assert_eq!(tlv(&[0x30, 0x84, 0xff, 0xff, 0xff, 0xff]), None);
+    }
+
+    #[test]
+    fn elements_keep_the_raw_encoding() {
+        let der = [encode(0x02, &[1]), encode(0x30, &encode(0x05, &[]))].concat();
+        assert_eq!(
+            elements(&der).unwrap(),
+            vec![
+                (0x02, &[1][..], &der[..3]),
+                (0x30, &[0x05, 0x00][..], &der[3..]),
+            ]
+        );
+        assert_eq!(elements(&[]).unwrap(), vec![]);
+        assert_eq!(elements(&der[..der.len() - 1]), None);
+    }
+
+    #[test]
+    fn oids() {
+        assert_eq!(oid(&[0x55, 0x1d, 0x11]), "2.5.29.17");
+        assert_eq!(
+            oid(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b]),
+            "1.2.840.113549.1.1.11"
+        );
+        assert_eq!(oid(&[0x2b, 0x65, 0x70]), "1.3.101.112");
+        assert_eq!(oid(&[0x88, 0x37]), "2.999");
+    }
+
+    #[test]
+    fn times() {
+        let epoch = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        assert_eq!(time(0x17, b"700101000000Z"), Some(epoch(0)));
+        assert_eq!(time(0x17, b"491231235959Z"), Some(epoch(2524607999)));
+        assert_eq!(time(0x18, b"20491231235959Z"), Some(epoch(2524607999)));
+        assert_eq!(time(0x18, b"20491231235959.5Z"), Some(epoch(2524607999)));
+        assert_eq!(time(0x17, b"491231235959"), None);
+        assert_eq!(time(0x18, b"20491331235959Z"), None);
+        assert_eq!(time(0x04, b"20491231235959Z"), None);
+        assert_eq!(time(0x17, b"\xffZ"), None);
+    }
+}
@@ -1,7 +1,10 @@
+use std::fmt;
+
 #[derive(Debug)]
 pub enum TlsConfigError {
     Certificate(String),
     PrivateKey(String),
+    Format(String),
     UnresolvablePath(String),
     TlsError(rustls::Error),
 }
@@ -17,3 +20,13 @@ impl From<rustls::Error> for TlsConfigError {
         TlsConfigError::TlsError(e)
     }
 }
+
+impl fmt::Display for TlsConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Certificate(v) | Self::PrivateKey(v) | Self::Format(v) => write!(f, "{}", v),
+            Self::UnresolvablePath(v) => write!(f, "unable to read {}", v),
+            Self::TlsError(e) => write!(f, "{}", e),
+        }
+    }
+}
@@ -1,2 +1,14 @@
+pub mod client;
+pub mod der;
 pub mod err;
+pub mod ocsp;
+pub mod params;
+pub mod passphrase;
+pub mod pem;
+pub mod selfsigned;
+pub mod session;
+pub mod sni;
+#[cfg(test)]
+pub mod testdata;
 pub mod tls;
+pub mod x509;
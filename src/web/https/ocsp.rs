@@ -0,0 +1,265 @@
+use crate::web::https::{
+    der::{elements, oid, time, tlv},
+    x509::Tbs,
+};
+use ring::digest;
+use std::time::{Duration, SystemTime};
+
+const BASIC_RESPONSE: &str = "1.3.6.1.5.5.7.48.1.1";
+pub const CLOCK_SKEW: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CertStatus {
+    Good,
+    Revoked,
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct OcspResponse {
+    pub der: Vec<u8>,
+    pub status: CertStatus,
+    pub this_update: SystemTime,
+    pub next_update: SystemTime,
+}
+
+impl OcspResponse {
+    pub fn parse(der: &[u8], cert: &[u8], issuer: Option<&[u8]>) -> Result<OcspResponse, String> {
+        let malformed = || String::from("malformed OCSP response");
+        let tbs = Tbs::parse(cert).ok_or_else(|| String::from("invalid certificate"))?;
+        let issuer_key = issuer
+            .and_then(Tbs::parse)
+            .filter(|v| v.subject == tbs.issuer)
+            .map(|v| v.public_key);
+        let response = match tlv(der) {
+            Some((0x30, v, _)) => v,
+            _ => return Err(malformed()),
+        };
+        let bytes = match elements(response).ok_or_else(malformed)?.as_slice() {
+            [(0x0a, [0], _), (0xa0, bytes, _)] => *bytes,
+            [(0x0a, status, _), ..] => return Err(status_error(status)),
+            _ => return Err(malformed()),
+        };
+        let (_, bytes, _) = tlv(bytes).ok_or_else(malformed)?;
+        let basic = match elements(bytes).ok_or_else(malformed)?.as_slice() {
+            [(0x06, kind, _), (0x04, basic, _)] if oid(kind) == BASIC_RESPONSE => *basic,
+            _ => return Err(String::from("unsupported OCSP response type")),
+        };
+        let (_, basic, _) = tlv(basic).ok_or_else(malformed)?;
+        let data = match elements(basic).ok_or_else(malformed)?.first() {
+            Some((0x30, v, _)) => *v,
+            _ => return Err(malformed()),
+        };
+        let responses = match elements(data)
+            .ok_or_else(malformed)?
+            .iter()
+            .skip_while(|(tag, _, _)| *tag != 0x18)
+            .nth(1)
+        {
+            Some((0x30, v, _)) => *v,
+            _ => return Err(malformed()),
+        };
+
+        for (_, single, _) in elements(responses).ok_or_else(malformed)? {
+            let single = elements(single).ok_or_else(malformed)?;
+            let (id, status, this_update, rest) = match single.as_slice() {
+                [(0x30, id, _), (status, _, _), (0x18, this_update, _), rest @ ..] => {
+                    (*id, *status, *this_update, rest)
+                }
+                _ => return Err(malformed()),
+            };
+            let id = elements(id).ok_or_else(malformed)?;
+            let (algorithm, name_hash, key_hash, serial) = match id.as_slice() {
+                [(0x30, algorithm, _), (0x04, name_hash, _), (0x04, key_hash, _), (0x02, serial, _)] => {
+                    (*algorithm, *name_hash, *key_hash, *serial)
+                }
+                _ => return Err(malformed()),
+            };
+            let algorithm = hash_algorithm(algorithm)?;
+            if serial != tbs.serial
+                || digest::digest(algorithm, tbs.issuer).as_ref() != name_hash
+                || issuer_key.is_some_and(|v| digest::digest(algorithm, v).as_ref() != key_hash)
+            {
+                continue;
+            }
+            let status = match status {
+                0x80 => CertStatus::Good,
+                0xa1 => CertStatus::Revoked,
+                _ => CertStatus::Unknown,
+            };
+            let next_update = match rest.iter().find(|(tag, _, _)| *tag == 0xa0) {
+                Some((_, v, _)) => match tlv(v) {
+                    Some((0x18, v, _)) => time(0x18, v).ok_or_else(malformed)?,
+                    _ => return Err(malformed()),
+                },
+                None => return Err(String::from("response has no next update time")),
+            };
+            return Ok(OcspResponse {
+                der: der.to_vec(),
+                status,
+                this_update: time(0x18, this_update).ok_or_else(malformed)?,
+                next_update,
+            });
+        }
+        Err(String::from("response is for a different certificate"))
+    }
+    pub fn check(&self, now: SystemTime) -> Result<(), String> {
+        if self.status == CertStatus::Revoked {
+            Err(String::from("the certificate is revoked"))
+        } else if self.status == CertStatus::Unknown {
+            Err(String::from("the responder doesn't know the certificate"))
+        } else if self.this_update > now + CLOCK_SKEW {
+            Err(String::from("response is not valid yet"))
+        } else if self.next_update <= now {
+            Err(String::from("response has expired"))
+        } else {
+            Ok(())
+        }
+    }
+    pub fn changes_at(&self, now: SystemTime) -> Option<SystemTime> {
+        match self.status {
+            CertStatus::Good if self.this_update > now + CLOCK_SKEW => {
+                Some(self.this_update - CLOCK_SKEW)
+            }
+            CertStatus::Good if self.next_update > now => Some(self.next_update),
+            _ => None,
+        }
+    }
+}
+
+fn hash_algorithm(algorithm: &[u8]) -> Result<&'static digest::Algorithm, String> {
+    match elements(algorithm).as_deref() {
+        Some([(0x06, v, _), ..]) => match oid(v).as_str() {
+            "1.3.14.3.2.26" => Ok(&digest::SHA1_FOR_LEGACY_USE_ONLY),
+            "2.16.840.1.101.3.4.2.1" => Ok(&digest::SHA256),
+            "2.16.840.1.101.3.4.2.2" => Ok(&digest::SHA384),
+            "2.16.840.1.101.3.4.2.3" => Ok(&digest::SHA512),
+            v => Err(format!("unsupported OCSP hash algorithm {}", v)),
+        },
+        _ => Err(String::from("malformed OCSP response")),
+    }
+}
+
+fn status_error(status: &[u8]) -> String {
+    let status = match status {
+        [1] => "malformed request",
+        [2] => "internal error",
+        [3] => "try later",
+        [5] => "signature required",
+        [6] => "unauthorized",
+        _ => "unknown status",
+    };
+    format!("OCSP responder answered: {}", status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::https::testdata::{cert, read};
+
+    const WEEK: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+    #[test]
+    fn good_response_for_the_leaf() {
+        let response = OcspResponse::parse(
+            &read("ocsp-leaf.der"),
+            &cert("leaf.pem"),
+            Some(&cert("ca.pem")),
+        )
+        .unwrap();
+        assert_eq!(response.status, CertStatus::Good);
+        assert_eq!(response.der, read("ocsp-leaf.der"));
+        assert_eq!(
+            response
+                .next_update
+                .duration_since(response.this_update)
+                .unwrap(),
+            WEEK
+        );
+    }
+
+    #[test]
+    fn issuer_is_optional() {
+        let response =
+            OcspResponse::parse(&read("ocsp-leaf.der"), &cert("leaf.pem"), None).unwrap();
+        assert_eq!(response.status, CertStatus::Good);
+    }
+
+    #[test]
+    fn revoked_response() {
+        let response =
+            OcspResponse::parse(&read("ocsp-revoked.der"), &cert("revoked.pem"), None).unwrap();
+        assert_eq!(response.status, CertStatus::Revoked);
+        assert!(response.check(response.this_update).is_err());
+        assert_eq!(response.changes_at(response.this_update), None);
+    }
+
+    #[test]
+    fn response_for_another_certificate() {
+        let error = OcspResponse::parse(&read("ocsp-leaf.der"), &cert("revoked.pem"), None);
+        assert_eq!(
+            error.unwrap_err(),
+            "response is for a different certificate"
+        );
+    }
+
+    #[test]
+    fn response_from_another_issuer_key() {
+        let error = OcspResponse::parse(
+            &read("ocsp-leaf.der"),
+            &cert("leaf.pem"),
+            Some(&cert("other-ca.pem")),
+        );
+        assert_eq!(
+            error.unwrap_err(),
+            "response is for a different certificate"
+        );
+    }
+
+    #[test]
+    fn issuer_with_another_subject_is_ignored() {
+        let response = OcspResponse::parse(
+            &read("ocsp-leaf.der"),
+            &cert("leaf.pem"),
+            Some(&cert("revoked.pem")),
+        );
+        assert!(response.is_ok());
+    }
+
+    #[test]
+    fn validity_period() {
+        let response =
+            OcspResponse::parse(&read("ocsp-leaf.der"), &cert("leaf.pem"), None).unwrap();
+        let this_update = response.this_update;
+        assert!(response.check(this_update).is_ok());
+        assert!(response.check(this_update - CLOCK_SKEW).is_ok());
+        assert!(response.check(this_update - CLOCK_SKEW * 2).is_err());
+        assert!(response.check(response.next_update).is_err());
+        assert_eq!(
+            response.changes_at(this_update - CLOCK_SKEW * 2),
+            Some(this_update - CLOCK_SKEW)
+        );
+        assert_eq!(response.changes_at(this_update), Some(response.next_update));
+        assert_eq!(response.changes_at(response.next_update), None);
+    }
+
+    #[test]
+    fn truncated_response_is_rejected() {
+        let der = read("ocsp-leaf.der");
+        for len in 0..der.len() {
+            assert!(OcspResponse::parse(&der[..len], &cert("leaf.pem"), None).is_err());
+        }
+    }
+
+    #[test]
+    fn malformed_response_is_rejected() {
+        let leaf = cert("leaf.pem");
+        assert_eq!(
+            OcspResponse::parse(&[0x30, 0x03, 0x0a, 0x01, 0x03], &leaf, None).unwrap_err(),
+            "OCSP responder answered: try later"
+        );
+        assert!(OcspResponse::parse(&read("crl.der"), &leaf, None).is_err());
+        assert!(OcspResponse::parse(b"garbage", &leaf, None).is_err());
+        assert!(OcspResponse::parse(&read("ocsp-leaf.der"), b"garbage", None).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use rustls::{SupportedCipherSuite, SupportedKxGroup, SupportedProtocolVersion};
+use std::{fmt, str::FromStr};
+
+pub const ALPN_PROTOCOLS: &[&str] = &["http/1.1", "http/1.0"];
+
+static TLS13_ONLY: &[&SupportedProtocolVersion] = &[&rustls::version::TLS13];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TlsVersion {
+    Tls12,
+    Tls13,
+}
+
+impl TlsVersion {
+    pub fn versions(&self) -> &'static [&'static SupportedProtocolVersion] {
+        match self {
+            Self::Tls12 => rustls::ALL_VERSIONS,
+            Self::Tls13 => TLS13_ONLY,
+        }
+    }
+}
+
+impl FromStr for TlsVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1.2" => Ok(Self::Tls12),
+            "1.3" => Ok(Self::Tls13),
+            _ => Err(String::from("expected 1.2 or 1.3")),
+        }
+    }
+}
+
+impl fmt::Display for TlsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tls12 => write!(f, "1.2"),
+            Self::Tls13 => write!(f, "1.3"),
+        }
+    }
+}
+
+pub fn cipher_suite(name: &str) -> Result<SupportedCipherSuite, String> {
+    rustls::ALL_CIPHER_SUITES
+        .iter()
+        .find(|v| format!("{:?}", v.suite()).eq_ignore_ascii_case(name))
+        .copied()
+        .ok_or_else(|| {
+            format!(
+                "unknown cipher suite, expected one of: {}",
+                rustls::ALL_CIPHER_SUITES
+                    .iter()
+                    .map(|v| format!("{:?}", v.suite()))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+        })
+}
+
+pub fn kx_group(name: &str) -> Result<&'static SupportedKxGroup, String> {
+    rustls::ALL_KX_GROUPS
+        .iter()
+        .find(|v| format!("{:?}", v.name).eq_ignore_ascii_case(name))
+        .copied()
+        .ok_or_else(|| {
+            format!(
+                "unknown key exchange group, expected one of: {}",
+                rustls::ALL_KX_GROUPS
+                    .iter()
+                    .map(|v| format!("{:?}", v.name))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+        })
+}
+
+pub fn alpn_protocol(name: &str) -> Result<String, String> {
+    match name {
+        v if ALPN_PROTOCOLS.contains(&v) => Ok(v.to_owned()),
+        "h2" => Err(String::from(
+            "h2 is not supported, the server only speaks http/1.x",
+        )),
+        _ => Err(format!(
+            "unsupported protocol, expected one of: {}",
+            ALPN_PROTOCOLS.join(", ")
+        )),
+    }
+}
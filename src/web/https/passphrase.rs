@@ -0,0 +1,240 @@
+use crate::web::TlsConfigError;
+use log::debug;
+use pkcs8::EncryptedPrivateKeyInfo;
+use rustls::sign::SigningKey;
+use std::{
+    fmt,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+use zeroize::{Zeroize, Zeroizing};
+
+const CAPACITY: usize = 4096;
+
+static DECRYPTED: Mutex<Vec<Decrypted>> = Mutex::new(Vec::new());
+static CONSUMED: Mutex<Vec<Passphrase>> = Mutex::new(Vec::new());
+static ENVIRONMENT: Mutex<Vec<(String, Secret)>> = Mutex::new(Vec::new());
+
+pub type Secret = Zeroizing<String>;
+type Decrypted = (PathBuf, Vec<u8>, Arc<dyn SigningKey>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Passphrase {
+    Env(String),
+    Fd(i32),
+    File(PathBuf),
+    Prompt,
+}
+
+impl FromStr for Passphrase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("env", v)) if !v.is_empty() => Ok(Self::Env(v.to_owned())),
+            Some(("fd", v)) => match v.parse::<i32>() {
+                Ok(v) if v >= 0 => Ok(Self::Fd(v)),
+                _ => Err(format!("invalid file descriptor: {}", v)),
+            },
+            Some(("file", v)) if !v.is_empty() => Ok(Self::File(PathBuf::from(v))),
+            None if s == "prompt" => Ok(Self::Prompt),
+            _ => Err(String::from(
+                "expected one of env:NAME, fd:NUMBER, file:PATH or prompt",
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Passphrase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Env(v) => write!(f, "env:{}", v),
+            Self::Fd(v) => write!(f, "fd:{}", v),
+            Self::File(v) => write!(f, "file:{}", v.display()),
+            Self::Prompt => write!(f, "prompt"),
+        }
+    }
+}
+
+impl Passphrase {
+    pub fn read(&self) -> Result<Secret, String> {
+        if self.is_one_shot() {
+            let mut consumed = CONSUMED.lock().unwrap_or_else(|e| e.into_inner());
+            if consumed.contains(self) {
+                return Err(String::from(
+                    "the passphrase was already read, restart to load a changed encrypted key",
+                ));
+            }
+            consumed.push(self.clone());
+        }
+        let mut secret = match self {
+            Self::Env(name) => {
+                let mut environment = ENVIRONMENT.lock().unwrap_or_else(|e| e.into_inner());
+                match environment.iter().position(|(v, _)| v == name) {
+                    Some(i) => environment.swap_remove(i).1,
+                    None => return Err(std::env::VarError::NotPresent.to_string()),
+                }
+            }
+            Self::Fd(fd) => read_fd(*fd)?,
+            Self::File(path) => {
+                read_limited(std::fs::File::open(path).map_err(|e| e.to_string())?)?
+            }
+            Self::Prompt => Zeroizing::new(
+                rpassword::prompt_password("private key passphrase: ")
+                    .map_err(|e| format!("unable to prompt on the terminal: {}", e))?,
+            ),
+        };
+        let len = secret.trim_end_matches(&['\r', '\n'][..]).len();
+        secret.truncate(len);
+        if secret.is_empty() {
+            return Err(String::from("the passphrase is empty"));
+        }
+        debug!("read private key passphrase from {}", self);
+        Ok(secret)
+    }
+    pub fn take_env(&self) {
+        if let Self::Env(name) = self {
+            if let Some(v) = std::env::var_os(name) {
+                std::env::remove_var(name);
+                if let Ok(v) = v.into_string() {
+                    let mut environment = ENVIRONMENT.lock().unwrap_or_else(|e| e.into_inner());
+                    environment.retain(|(n, _)| n != name);
+                    environment.push((name.clone(), Zeroizing::new(v)));
+                }
+            }
+        }
+    }
+    fn is_one_shot(&self) -> bool {
+        !matches!(self, Self::File(_))
+    }
+}
+
+#[derive(Debug)]
+pub struct Unlocker {
+    source: Option<Passphrase>,
+    secret: Option<Secret>,
+}
+
+impl Unlocker {
+    pub fn new(source: Option<Passphrase>) -> Unlocker {
+        Unlocker {
+            source,
+            secret: None,
+        }
+    }
+    pub fn decrypt_key(
+        &mut self,
+        path: &Path,
+        der: &[u8],
+    ) -> Result<Arc<dyn SigningKey>, TlsConfigError> {
+        let mut decrypted = DECRYPTED.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, _, key)) = decrypted.iter().find(|(p, d, _)| p == path && d == der) {
+            return Ok(Arc::clone(key));
+        }
+        let mut plain = decrypt(der, self.secret(path)?)
+            .map_err(|e| TlsConfigError::PrivateKey(format!("{}: {}", path.display(), e)))?;
+        let key = signing_key(rustls::PrivateKey(std::mem::take(&mut *plain)))?;
+        debug!("decrypted private key {}", path.display());
+        decrypted.retain(|(p, _, _)| p != path);
+        decrypted.push((path.to_path_buf(), der.to_vec(), Arc::clone(&key)));
+        Ok(key)
+    }
+    fn secret(&mut self, path: &Path) -> Result<&Secret, TlsConfigError> {
+        let source = match &self.source {
+            Some(v) => v,
+            None => {
+                return Err(TlsConfigError::PrivateKey(format!(
+                    "{} is encrypted, set --https-key-passphrase",
+                    path.display()
+                )))
+            }
+        };
+        let secret = match self.secret.take() {
+            Some(v) => v,
+            None => source.read().map_err(|e| {
+                TlsConfigError::PrivateKey(format!("key passphrase {}: {}", source, e))
+            })?,
+        };
+        Ok(self.secret.insert(secret))
+    }
+}
+
+pub fn decrypt(der: &[u8], secret: &Secret) -> Result<Zeroizing<Vec<u8>>, String> {
+    let info = EncryptedPrivateKeyInfo::try_from(der).map_err(|e| {
+        format!(
+            "invalid or unsupported encrypted private key, only PBES2 is supported: {}",
+            e
+        )
+    })?;
+    let document = info.decrypt(secret.as_bytes()).map_err(|e| match e {
+        pkcs8::Error::EncryptedPrivateKey(
+            pkcs8::pkcs5::Error::DecryptFailed | pkcs8::pkcs5::Error::EncryptFailed,
+        ) => String::from("wrong passphrase"),
+        pkcs8::Error::Asn1(e) => format!(
+            "the decrypted private key is malformed, the passphrase may be wrong: {}",
+            e
+        ),
+        e => format!("unable to decrypt the private key: {}", e),
+    })?;
+    Ok(Zeroizing::new(document.as_bytes().to_vec()))
+}
+
+pub fn signing_key(mut key: rustls::PrivateKey) -> Result<Arc<dyn SigningKey>, TlsConfigError> {
+    let signing_key = rustls::sign::any_supported_type(&key);
+    key.0.zeroize();
+    signing_key
+        .map_err(|_| TlsConfigError::PrivateKey(String::from("unsupported private key type")))
+}
+
+fn read_limited(mut reader: impl Read) -> Result<Secret, String> {
+    let mut buf = Zeroizing::new(vec![0u8; CAPACITY + 1]);
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    if len > CAPACITY {
+        return Err(format!("the passphrase is longer than {} bytes", CAPACITY));
+    }
+    std::str::from_utf8(&buf[..len])
+        .map(|v| Zeroizing::new(v.to_owned()))
+        .map_err(|_| String::from("the passphrase is not valid UTF-8"))
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<Secret, String> {
+    use std::os::unix::io::FromRawFd;
+
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } < 0 {
+        return Err(format!("file descriptor {} is not open", fd));
+    }
+    read_limited(unsafe { std::fs::File::from_raw_fd(fd) })
+}
+
+#[cfg(not(unix))]
+fn read_fd(_: i32) -> Result<Secret, String> {
+    Err(String::from("file descriptors are only supported on unix"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::https::testdata::temp;
+
+    #[test]
+    fn file_passphrase_is_limited() {
+        let path = temp("passphrase-max", "x".repeat(CAPACITY).as_bytes());
+        assert_eq!(Passphrase::File(path).read().unwrap().len(), CAPACITY);
+        let path = temp("passphrase-long", "x".repeat(CAPACITY + 1).as_bytes());
+        assert_eq!(
+            Passphrase::File(path).read().unwrap_err(),
+            "the passphrase is longer than 4096 bytes"
+        );
+    }
+}
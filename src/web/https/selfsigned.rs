@@ -0,0 +1,133 @@
+use crate::web::{https::pem::to_pem, TlsConfigError};
+use chrono::{DateTime, Datelike, Duration, Utc};
+use log::{info, warn};
+use rcgen::{
+    date_time_ymd, CertificateParams, DnType, ExtendedKeyUsagePurpose, KeyUsagePurpose, SanType,
+};
+use ring::{digest, rand::SecureRandom};
+use rustls::sign::CertifiedKey;
+use std::{
+    net::IpAddr,
+    sync::{Arc, OnceLock},
+};
+
+static SELF_SIGNED: OnceLock<Result<SelfSigned, String>> = OnceLock::new();
+
+pub struct SelfSigned {
+    pub cert: rustls::Certificate,
+    pub key: rustls::PrivateKey,
+    pub hosts: Vec<String>,
+    pub not_after: DateTime<Utc>,
+}
+
+impl SelfSigned {
+    pub fn generate(hosts: &[String], days: u32) -> Result<SelfSigned, TlsConfigError> {
+        let error = |e: rcgen::RcgenError| {
+            TlsConfigError::Certificate(format!("unable to generate a certificate: {}", e))
+        };
+        let first = match hosts.first() {
+            Some(v) => v,
+            None => {
+                return Err(TlsConfigError::Certificate(String::from(
+                    "a self-signed certificate needs at least one host name",
+                )))
+            }
+        };
+        let mut serial = [0u8; 8];
+        ring::rand::SystemRandom::new()
+            .fill(&mut serial)
+            .map_err(|_| TlsConfigError::Certificate(String::from("no randomness available")))?;
+        let now = Utc::now();
+        let not_after = match now.checked_add_signed(Duration::days(days as i64)) {
+            Some(v) if v.year() <= 9999 => v,
+            _ => {
+                return Err(TlsConfigError::Certificate(format!(
+                    "a certificate valid for {} days would expire after the year 9999",
+                    days
+                )))
+            }
+        };
+        let date = |v: DateTime<Utc>| date_time_ymd(v.year(), v.month() as u8, v.day() as u8);
+
+        let mut params = CertificateParams::default();
+        params.distinguished_name.remove(DnType::CommonName);
+        params.distinguished_name.push(DnType::CommonName, first);
+        params
+            .distinguished_name
+            .push(DnType::OrganizationName, "twebd");
+        params.subject_alt_names = hosts
+            .iter()
+            .map(|v| match v.parse::<IpAddr>() {
+                Ok(ip) => SanType::IpAddress(ip),
+                Err(_) => SanType::DnsName(v.to_owned()),
+            })
+            .collect();
+        params.serial_number = Some(u64::from_be_bytes(serial) >> 1);
+        params.not_before = date(now - Duration::days(1));
+        params.not_after = date(not_after);
+        params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+
+        let cert = rcgen::Certificate::from_params(params).map_err(error)?;
+        Ok(SelfSigned {
+            cert: rustls::Certificate(cert.serialize_der().map_err(error)?),
+            key: rustls::PrivateKey(cert.serialize_private_key_der()),
+            hosts: hosts.to_vec(),
+            not_after,
+        })
+    }
+    pub fn shared(hosts: &[String], days: u32) -> Result<&'static SelfSigned, TlsConfigError> {
+        SELF_SIGNED
+            .get_or_init(|| {
+                let cert = SelfSigned::generate(hosts, days).map_err(|e| e.to_string())?;
+                warn!("using an in-memory self-signed certificate, for development only");
+                info!(
+                    "generated certificate for {}, valid until {}, sha256 fingerprint {}",
+                    cert.hosts.join(", "),
+                    cert.not_after.format("%Y-%m-%d"),
+                    cert.fingerprint()
+                );
+                Ok(cert)
+            })
+            .as_ref()
+            .map_err(|e| TlsConfigError::Certificate(e.to_owned()))
+    }
+    pub fn certified_key(&self) -> Result<Arc<CertifiedKey>, TlsConfigError> {
+        let key = rustls::sign::any_supported_type(&self.key).map_err(|_| {
+            TlsConfigError::PrivateKey(String::from("unsupported private key type"))
+        })?;
+        Ok(Arc::new(CertifiedKey::new(vec![self.cert.clone()], key)))
+    }
+    pub fn fingerprint(&self) -> String {
+        digest::digest(&digest::SHA256, &self.cert.0)
+            .as_ref()
+            .iter()
+            .map(|v| format!("{:02X}", v))
+            .collect::<Vec<String>>()
+            .join(":")
+    }
+    pub fn cert_pem(&self) -> String {
+        to_pem("CERTIFICATE", &self.cert.0)
+    }
+    pub fn key_pem(&self) -> String {
+        to_pem("PRIVATE KEY", &self.key.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validity_past_year_9999_is_rejected() {
+        let hosts = vec![String::from("localhost")];
+        let cert = SelfSigned::generate(&hosts, 30).unwrap();
+        assert_eq!(cert.hosts, hosts);
+        for days in [3_000_000, u32::MAX] {
+            match SelfSigned::generate(&hosts, days) {
+                Err(TlsConfigError::Certificate(e)) => assert!(e.contains("year 9999"), "{}", e),
+                _ => panic!("{} days should be rejected", days),
+            }
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use crate::srv::ConnectionError;
+use log::{debug, info};
+use ring::{aead, rand::SecureRandom};
+use rustls::server::{
+    NoServerSessionStorage, ProducesTickets, ServerSessionMemoryCache, StoresServerSessions,
+};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+const NONCE_LEN: usize = 12;
+const STATS_INTERVAL: Duration = Duration::from_secs(60);
+
+static SESSIONS: OnceLock<Sessions> = OnceLock::new();
+
+pub struct Sessions {
+    pub ticketer: Option<Arc<Ticketer>>,
+    pub cache: Arc<dyn StoresServerSessions + Send + Sync>,
+    pub stats: Arc<SessionStats>,
+    logged: Mutex<(Instant, SessionSnapshot)>,
+}
+
+impl Sessions {
+    pub fn shared(tickets: Option<Duration>, cache_size: usize) -> &'static Sessions {
+        SESSIONS.get_or_init(|| {
+            let stats = Arc::new(SessionStats::default());
+            let cache: Arc<dyn StoresServerSessions + Send + Sync> = match cache_size {
+                0 => Arc::new(NoServerSessionStorage {}),
+                n => Arc::new(SessionCache {
+                    inner: ServerSessionMemoryCache::new(n),
+                    stats: Arc::clone(&stats),
+                }),
+            };
+            debug!(
+                "tls session tickets {}, session cache size {}",
+                match tickets {
+                    Some(v) => format!("rotated every {}s", v.as_secs()),
+                    None => String::from("disabled"),
+                },
+                cache_size
+            );
+            Sessions {
+                ticketer: tickets.map(|v| Arc::new(Ticketer::new(v, Arc::clone(&stats)))),
+                cache,
+                stats,
+                logged: Mutex::new((Instant::now(), SessionSnapshot::default())),
+            }
+        })
+    }
+    pub fn log(&self, now: Instant) {
+        let mut logged = match self.logged.lock() {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        if now < logged.0 + STATS_INTERVAL {
+            return;
+        }
+        let snapshot = self.snapshot();
+        if snapshot != logged.1 {
+            info!("tls sessions: {}", snapshot);
+        }
+        *logged = (now, snapshot);
+    }
+    pub fn snapshot(&self) -> SessionSnapshot {
+        self.stats.snapshot()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SessionStats {
+    pub handshakes: AtomicU64,
+    pub ticket_resumptions: AtomicU64,
+    pub ticket_rejections: AtomicU64,
+    pub cache_resumptions: AtomicU64,
+    pub cache_misses: AtomicU64,
+    pub failures: AtomicU64,
+    pub protocol_mismatches: AtomicU64,
+    pub server_name_failures: AtomicU64,
+    pub unknown_ca_alerts: AtomicU64,
+}
+
+impl SessionStats {
+    pub fn handshake(&self) {
+        self.handshakes.fetch_add(1, Ordering::Relaxed);
+    }
+    pub fn failure(&self, e: &ConnectionError) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        let counter = match e {
+            ConnectionError::TlsProtocolMismatch(_) => &self.protocol_mismatches,
+            ConnectionError::TlsServerName(_) => &self.server_name_failures,
+            ConnectionError::TlsUnknownCa(_) => &self.unknown_ca_alerts,
+            _ => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+    pub fn snapshot(&self) -> SessionSnapshot {
+        let get = |v: &AtomicU64| v.load(Ordering::Relaxed);
+        SessionSnapshot {
+            handshakes: get(&self.handshakes),
+            ticket_resumptions: get(&self.ticket_resumptions),
+            ticket_rejections: get(&self.ticket_rejections),
+            cache_resumptions: get(&self.cache_resumptions),
+            cache_misses: get(&self.cache_misses),
+            failures: get(&self.failures),
+            protocol_mismatches: get(&self.protocol_mismatches),
+            server_name_failures: get(&self.server_name_failures),
+            unknown_ca_alerts: get(&self.unknown_ca_alerts),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SessionSnapshot {
+    pub handshakes: u64,
+    pub ticket_resumptions: u64,
+    pub ticket_rejections: u64,
+    pub cache_resumptions: u64,
+    pub cache_misses: u64,
+    pub failures: u64,
+    pub protocol_mismatches: u64,
+    pub server_name_failures: u64,
+    pub unknown_ca_alerts: u64,
+}
+
+impl SessionSnapshot {
+    pub fn resumed(&self) -> u64 {
+        self.ticket_resumptions + self.cache_resumptions
+    }
+    pub fn full(&self) -> u64 {
+        self.handshakes.saturating_sub(self.resumed())
+    }
+}
+
+impl fmt::Display for SessionSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} handshakes, {} full, {} resumed ({} ticket, {} cache), {} rejected tickets, {} cache misses, \
+             {} failed ({} protocol mismatch, {} server name, {} unknown ca)",
+            self.handshakes,
+            self.full(),
+            self.resumed(),
+            self.ticket_resumptions,
+            self.cache_resumptions,
+            self.ticket_rejections,
+            self.cache_misses,
+            self.failures,
+            self.protocol_mismatches,
+            self.server_name_failures,
+            self.unknown_ca_alerts
+        )
+    }
+}
+
+struct SessionCache {
+    inner: Arc<ServerSessionMemoryCache>,
+    stats: Arc<SessionStats>,
+}
+
+impl SessionCache {
+    fn count(&self, value: Option<Vec<u8>>) -> Option<Vec<u8>> {
+        match &value {
+            Some(_) => self.stats.cache_resumptions.fetch_add(1, Ordering::Relaxed),
+            None => self.stats.cache_misses.fetch_add(1, Ordering::Relaxed),
+        };
+        value
+    }
+}
+
+impl StoresServerSessions for SessionCache {
+    fn put(&self, key: Vec<u8>, value: Vec<u8>) -> bool {
+        self.inner.put(key, value)
+    }
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.count(self.inner.get(key))
+    }
+    fn take(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.count(self.inner.take(key))
+    }
+    fn can_cache(&self) -> bool {
+        self.inner.can_cache()
+    }
+}
+
+struct TicketKeys {
+    current: aead::LessSafeKey,
+    previous: Option<aead::LessSafeKey>,
+    rotate_at: Instant,
+}
+
+pub struct Ticketer {
+    rotation: Duration,
+    random: ring::rand::SystemRandom,
+    keys: Mutex<Option<TicketKeys>>,
+    stats: Arc<SessionStats>,
+}
+
+impl Ticketer {
+    pub fn new(rotation: Duration, stats: Arc<SessionStats>) -> Ticketer {
+        Ticketer {
+            rotation,
+            random: ring::rand::SystemRandom::new(),
+            keys: Mutex::new(None),
+            stats,
+        }
+    }
+    fn key(&self) -> Option<aead::LessSafeKey> {
+        let mut key = [0u8; 32];
+        self.random.fill(&mut key).ok()?;
+        let key = aead::UnboundKey::new(&aead::CHACHA20_POLY1305, &key).ok()?;
+        Some(aead::LessSafeKey::new(key))
+    }
+    fn rotate(&self, keys: &mut Option<TicketKeys>, now: Instant) -> Option<()> {
+        match keys {
+            Some(v) if now < v.rotate_at => {}
+            Some(v) => {
+                let current = self.key()?;
+                let expired = now >= v.rotate_at + self.rotation;
+                let previous = std::mem::replace(&mut v.current, current);
+                v.previous = if expired { None } else { Some(previous) };
+                v.rotate_at = now + self.rotation;
+                debug!("rotated tls session ticket key");
+            }
+            None => {
+                *keys = Some(TicketKeys {
+                    current: self.key()?,
+                    previous: None,
+                    rotate_at: now + self.rotation,
+                })
+            }
+        }
+        Some(())
+    }
+    fn encrypt_at(&self, plain: &[u8], now: Instant) -> Option<Vec<u8>> {
+        let mut keys = self.keys.lock().ok()?;
+        self.rotate(&mut keys, now)?;
+        let mut nonce = [0u8; NONCE_LEN];
+        self.random.fill(&mut nonce).ok()?;
+        let mut out = plain.to_vec();
+        keys.as_ref()?
+            .current
+            .seal_in_place_append_tag(
+                aead::Nonce::assume_unique_for_key(nonce),
+                aead::Aad::empty(),
+                &mut out,
+            )
+            .ok()?;
+        Some([&nonce[..], &out].concat())
+    }
+    fn decrypt_at(&self, cipher: &[u8], now: Instant) -> Option<Vec<u8>> {
+        let mut keys = self.keys.lock().ok()?;
+        self.rotate(&mut keys, now)?;
+        let keys = keys.as_ref()?;
+        let plain = std::iter::once(&keys.current)
+            .chain(keys.previous.as_ref())
+            .find_map(|key| open(key, cipher));
+        match &plain {
+            Some(_) => self
+                .stats
+                .ticket_resumptions
+                .fetch_add(1, Ordering::Relaxed),
+            None => self.stats.ticket_rejections.fetch_add(1, Ordering::Relaxed),
+        };
+        plain
+    }
+}
+
+impl ProducesTickets for Ticketer {
+    fn enabled(&self) -> bool {
+        true
+    }
+    fn lifetime(&self) -> u32 {
+        self.rotation.as_secs().min(u32::MAX as u64) as u32
+    }
+    fn encrypt(&self, plain: &[u8]) -> Option<Vec<u8>> {
+        self.encrypt_at(plain, Instant::now())
+    }
+    fn decrypt(&self, cipher: &[u8]) -> Option<Vec<u8>> {
+        self.decrypt_at(cipher, Instant::now())
+    }
+}
+
+fn open(key: &aead::LessSafeKey, cipher: &[u8]) -> Option<Vec<u8>> {
+    if cipher.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, sealed) = cipher.split_at(NONCE_LEN);
+    let nonce = aead::Nonce::try_assume_unique_for_key(nonce).ok()?;
+    let mut out = sealed.to_vec();
+    let len = key
+        .open_in_place(nonce, aead::Aad::empty(), &mut out)
+        .ok()?
+        .len();
+    out.truncate(len);
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROTATION: Duration = Duration::from_secs(60);
+
+    fn ticketer() -> Ticketer {
+        Ticketer::new(ROTATION, Arc::new(SessionStats::default()))
+    }
+
+    #[test]
+    fn lifetime_matches_key_retention() {
+        assert_eq!(ticketer().lifetime() as u64, ROTATION.as_secs());
+    }
+
+    #[test]
+    fn tickets_are_accepted_for_their_lifetime() {
+        let ticketer = ticketer();
+        let start = Instant::now();
+        let lifetime = Duration::from_secs(ticketer.lifetime() as u64);
+        let first = ticketer.encrypt_at(b"first", start).unwrap();
+        let issued = start + ROTATION - Duration::from_secs(1);
+        let last = ticketer.encrypt_at(b"last", issued).unwrap();
+        assert_eq!(
+            ticketer.decrypt_at(&first, start + lifetime).as_deref(),
+            Some(&b"first"[..])
+        );
+        assert_eq!(
+            ticketer.decrypt_at(&last, issued + lifetime).as_deref(),
+            Some(&b"last"[..])
+        );
+    }
+
+    #[test]
+    fn tickets_expire_after_two_rotations() {
+        let ticketer = ticketer();
+        let start = Instant::now();
+        let ticket = ticketer.encrypt_at(b"ticket", start).unwrap();
+        assert!(ticketer.decrypt_at(&ticket, start + ROTATION).is_some());
+        assert!(ticketer.decrypt_at(&ticket, start + ROTATION * 2).is_none());
+    }
+
+    #[test]
+    fn idle_rotation_drops_the_previous_key() {
+        let ticketer = ticketer();
+        let start = Instant::now();
+        let ticket = ticketer.encrypt_at(b"ticket", start).unwrap();
+        assert!(ticketer.decrypt_at(&ticket, start + ROTATION * 3).is_none());
+    }
+
+    #[test]
+    fn snapshot_counts_resumptions() {
+        let ticketer = ticketer();
+        let start = Instant::now();
+        let ticket = ticketer.encrypt_at(b"ticket", start).unwrap();
+        ticketer.stats.handshake();
+        ticketer.stats.handshake();
+        assert!(ticketer.decrypt_at(&ticket, start).is_some());
+        assert!(ticketer.decrypt_at(b"forged", start).is_none());
+        let snapshot = ticketer.stats.snapshot();
+        assert_eq!(
+            snapshot,
+            SessionSnapshot {
+                handshakes: 2,
+                ticket_resumptions: 1,
+                ticket_rejections: 1,
+                ..SessionSnapshot::default()
+            }
+        );
+        assert_eq!((snapshot.full(), snapshot.resumed()), (1, 1));
+        assert!(snapshot.to_string().starts_with(
+            "2 handshakes, 1 full, 1 resumed (1 ticket, 0 cache), 1 rejected tickets"
+        ));
+    }
+}
@@ -0,0 +1,112 @@
+use crate::srv::route::wildcard;
+use log::{debug, info};
+use rustls::{
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+use std::sync::Arc;
+
+pub struct SniResolver {
+    hosts: Vec<(Vec<String>, Arc<CertifiedKey>)>,
+    default: Arc<CertifiedKey>,
+}
+
+impl SniResolver {
+    pub fn new(default: Arc<CertifiedKey>) -> SniResolver {
+        SniResolver {
+            hosts: Vec::new(),
+            default,
+        }
+    }
+    pub fn add(&mut self, names: Vec<String>, key: Arc<CertifiedKey>) {
+        self.hosts.push((names, key));
+    }
+    pub fn find(&self, name: &str) -> Option<&Arc<CertifiedKey>> {
+        if let Some((_, key)) = self
+            .hosts
+            .iter()
+            .find(|(names, _)| names.iter().any(|v| v.eq_ignore_ascii_case(name)))
+        {
+            return Some(key);
+        }
+        self.hosts
+            .iter()
+            .filter_map(|(names, key)| {
+                names
+                    .iter()
+                    .filter(|v| wildcard(v, name))
+                    .map(|v| (v.len(), key))
+                    .max_by_key(|(len, _)| *len)
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, key)| key)
+    }
+}
+
+impl ResolvesServerCert for SniResolver {
+    fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        match client_hello.server_name() {
+            Some(name) => match self.find(name) {
+                Some(key) => {
+                    debug!("selected certificate for server name {}", name);
+                    Some(Arc::clone(key))
+                }
+                None => {
+                    info!("no certificate for server name {}, using default", name);
+                    Some(Arc::clone(&self.default))
+                }
+            },
+            None => {
+                debug!("client sent no server name, using default certificate");
+                Some(Arc::clone(&self.default))
+            }
+        }
+    }
+}
+
+pub fn server_name(record: &[u8]) -> Option<Vec<u8>> {
+    names(record)?
+        .into_iter()
+        .find(|(kind, _)| *kind == 0)
+        .map(|(_, name)| name)
+}
+
+pub fn names(record: &[u8]) -> Option<Vec<(u8, Vec<u8>)>> {
+    let hello = match record {
+        [0x16, _, _, _, _, 0x01, _, _, _, rest @ ..] => rest.get(34..)?,
+        _ => return None,
+    };
+    let (_, hello) = split(hello, 1)?;
+    let (_, hello) = split(hello, 2)?;
+    let (_, hello) = split(hello, 1)?;
+    let (mut extensions, _) = split(hello, 2)?;
+    while !extensions.is_empty() {
+        let kind = extensions.get(..2)?;
+        let (data, rest) = split(&extensions[2..], 2)?;
+        extensions = rest;
+        if kind != [0, 0] {
+            continue;
+        }
+        let (mut list, _) = split(data, 2)?;
+        let mut names = Vec::new();
+        while let Some((&kind, rest)) = list.split_first() {
+            let (name, rest) = split(rest, 2)?;
+            names.push((kind, name.to_vec()));
+            list = rest;
+        }
+        return Some(names);
+    }
+    None
+}
+
+fn split(input: &[u8], size: usize) -> Option<(&[u8], &[u8])> {
+    let len = input
+        .get(..size)?
+        .iter()
+        .fold(0, |len, v| len << 8 | *v as usize);
+    let rest = &input[size..];
+    match rest.len() >= len {
+        true => Some(rest.split_at(len)),
+        false => None,
+    }
+}
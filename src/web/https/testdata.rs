@@ -0,0 +1,25 @@
+use crate::web::https::pem::{self, Item};
+use std::path::{Path, PathBuf};
+
+pub fn path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(name)
+}
+
+pub fn read(name: &str) -> Vec<u8> {
+    std::fs::read(path(name)).unwrap()
+}
+
+pub fn cert(name: &str) -> Vec<u8> {
+    match pem::read(&path(name)).unwrap().remove(0) {
+        Item::Certificate(v) => v.0,
+        v => panic!("expected a certificate in {}, got {:?}", name, v),
+    }
+}
+
+pub fn temp(name: &str, contents: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("twebd-{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
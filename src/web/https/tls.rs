@@ -1,107 +1,586 @@
 use crate::{
-    cli::{Builder, CliOpt},
-    web::TlsConfigError,
+    cli::{Builder, ServerConfig},
+    net::Listener,
+    srv::VirtualHost,
+    web::{
+        https::{
+            params::{cipher_suite, kx_group},
+            passphrase::{self, Unlocker},
+            pem::{self, Item},
+        },
+        ClientAuth, Crl, CrlVerifier, OcspResponse, Passphrase, SelfSigned, SessionStats, Sessions,
+        SniResolver, TlsConfigError, TlsVersion,
+    },
+};
+use log::{debug, error, info, trace, warn};
+use rustls::{
+    server::{
+        AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient, ClientCertVerifier,
+        ProducesTickets,
+    },
+    sign::{CertifiedKey, SigningKey},
+};
+use std::{
+    path::{Path, PathBuf},
+    result::Result,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
 };
-use log::{debug, trace};
-use std::{path::PathBuf, result::Result};
 
 #[derive(Debug)]
 pub struct TlsConfigBuilder {
-    https_enabled: bool,
     cert_path: PathBuf,
     priv_key_path: PathBuf,
-    _other: Vec<CliOpt>,
+    ocsp_path: Option<PathBuf>,
+    key_passphrase: Option<Passphrase>,
+    self_signed: Option<(Vec<String>, u32)>,
+    hosts: Vec<VirtualHost>,
+    client_auth: ClientAuth,
+    client_ca: Option<PathBuf>,
+    client_crl: Option<PathBuf>,
+    reload_interval: Duration,
+    min_version: TlsVersion,
+    cipher_suites: Vec<String>,
+    kx_groups: Vec<String>,
+    alpn: Vec<String>,
+    tickets: Option<Duration>,
+    session_cache: usize,
 }
 
 impl Builder<Self, rustls::ServerConfig, TlsConfigError> for TlsConfigBuilder {
-    fn new(opts: Vec<CliOpt>) -> Self {
-        let mut tls_config_builder = Self::default();
-        for opt in opts {
-            match opt {
-                CliOpt::Https(v) => tls_config_builder.https_enabled = v,
-                CliOpt::HttpsCert(v) => tls_config_builder.cert_path = v.unwrap(),
-                CliOpt::HttpsPrivKey(v) => tls_config_builder.priv_key_path = v.unwrap(),
-                cli_opt => tls_config_builder.add_other(cli_opt.to_owned()),
-            }
-        }
+    fn new(config: &ServerConfig, _listener: &Listener) -> Self {
+        let tls_config_builder = TlsConfigBuilder {
+            cert_path: config.https_cert.to_path_buf(),
+            priv_key_path: config.https_key.to_path_buf(),
+            ocsp_path: config.https_ocsp.clone(),
+            key_passphrase: config.https_key_passphrase.clone(),
+            self_signed: match config.https_self_signed {
+                true => Some((config.self_signed_hosts.to_vec(), config.self_signed_days)),
+                false => None,
+            },
+            hosts: config
+                .vhosts
+                .iter()
+                .filter(|v| v.https_cert.is_some() && v.https_key.is_some())
+                .cloned()
+                .collect(),
+            client_auth: config.client_auth,
+            client_ca: config.client_ca.clone(),
+            client_crl: config.client_crl.clone(),
+            reload_interval: config.cert_reload_interval,
+            min_version: config.tls_min_version,
+            cipher_suites: config.tls_ciphers.to_vec(),
+            kx_groups: config.tls_kx_groups.to_vec(),
+            alpn: config.alpn.to_vec(),
+            tickets: match config.tls_tickets {
+                true => Some(config.tls_ticket_rotation),
+                false => None,
+            },
+            session_cache: config.tls_session_cache,
+        };
 
         trace!("constructed tls config builder: `{:?}", &tls_config_builder);
         tls_config_builder
     }
     fn build(&self) -> Result<rustls::ServerConfig, TlsConfigError> {
-        let cert_chain = load_cert(&self.cert_path)?;
-        let priv_key = load_priv_key(&self.priv_key_path)?;
-        let server_config = rustls::ServerConfig::builder()
-            .with_safe_defaults()
-            .with_no_client_auth()
-            .with_single_cert(cert_chain, priv_key)?;
+        let mut unlocker = Unlocker::new(self.key_passphrase.clone());
+        let mut keys = Vec::new();
+        for host in &self.hosts {
+            if let (Some(cert), Some(key)) = (&host.https_cert, &host.https_key) {
+                let ocsp = host.https_ocsp.as_deref();
+                keys.push((host, certified_key(cert, key, ocsp, &mut unlocker)?));
+            }
+        }
+        let default = match keys.iter().find(|(host, _)| host.default) {
+            Some((_, key)) => Arc::clone(key),
+            None => match &self.self_signed {
+                Some((hosts, days)) => SelfSigned::shared(hosts, *days)?.certified_key()?,
+                None => certified_key(
+                    &self.cert_path,
+                    &self.priv_key_path,
+                    self.ocsp_path.as_deref(),
+                    &mut unlocker,
+                )?,
+            },
+        };
+        let mut resolver = SniResolver::new(default);
+        for (host, key) in keys {
+            debug!("loaded certificate for {:?}", &host.names);
+            resolver.add(host.names.to_vec(), key);
+        }
+        let mut server_config = match self.client_auth {
+            ClientAuth::None => self.protocols()?.with_no_client_auth(),
+            _ => self
+                .protocols()?
+                .with_client_cert_verifier(self.client_verifier()?),
+        }
+        .with_cert_resolver(Arc::new(resolver));
+        server_config.alpn_protocols = self.alpn.iter().map(|v| v.as_bytes().to_vec()).collect();
+        let sessions = Sessions::shared(self.tickets, self.session_cache);
+        server_config.session_storage = Arc::clone(&sessions.cache);
+        if let Some(v) = &sessions.ticketer {
+            server_config.ticketer = Arc::clone(v) as Arc<dyn ProducesTickets>;
+        }
         trace!("constructed server tls config");
         Ok(server_config)
     }
-    fn add_other(&mut self, o: CliOpt) {
-        self._other.push(o);
+}
+
+impl TlsConfigBuilder {
+    pub fn problems(&self) -> Vec<(&'static [&'static str], TlsConfigError)> {
+        let mut problems = Vec::new();
+        let mut unlocker = Unlocker::new(self.key_passphrase.clone());
+        for host in &self.hosts {
+            if let (Some(cert), Some(key)) = (&host.https_cert, &host.https_key) {
+                let ocsp = host.https_ocsp.as_deref();
+                if let Err(e) = certified_key(cert, key, ocsp, &mut unlocker) {
+                    problems.push((&[][..], e));
+                }
+            }
+        }
+        match &self.self_signed {
+            _ if self.hosts.iter().any(|v| v.default) => {}
+            Some((hosts, days)) => {
+                if let Err(e) = SelfSigned::shared(hosts, *days) {
+                    problems.push((&["self-signed-hosts", "self-signed-days"][..], e));
+                }
+            }
+            None => {
+                let cert_chain = match load_cert(&self.cert_path) {
+                    Ok(v) => Some(v),
+                    Err(e) => {
+                        problems.push((&["https-cert"][..], e));
+                        None
+                    }
+                };
+                let key = match load_priv_key(&self.priv_key_path, &mut unlocker) {
+                    Ok(v) => Some(v),
+                    Err(e) => {
+                        problems.push((&["https-key", "https-key-passphrase"][..], e));
+                        None
+                    }
+                };
+                if let (Some(cert_chain), Some(key)) = (&cert_chain, key) {
+                    if let Err(e) = verify_pair(&self.cert_path, cert_chain, key.as_ref()) {
+                        problems.push((&["https-cert", "https-key"][..], e));
+                    }
+                }
+                if let (Some(cert_chain), Some(ocsp)) = (&cert_chain, &self.ocsp_path) {
+                    if let Err(e) = load_ocsp(ocsp, cert_chain) {
+                        problems.push((&["https-ocsp"][..], e));
+                    }
+                }
+            }
+        }
+        if self.client_auth != ClientAuth::None {
+            match self.client_cas() {
+                Ok((cas, _)) => {
+                    if let Some(Err(e)) = self.client_crl.as_ref().map(|v| load_crls(v, &cas)) {
+                        problems.push((&["client-crl"][..], e));
+                    }
+                }
+                Err(e) => problems.push((&["client-ca"][..], e)),
+            }
+        }
+        if let Err(e) = self.protocols() {
+            problems.push((&["tls-ciphers", "tls-kx-groups", "tls-min-version"][..], e));
+        }
+        problems
+    }
+    fn protocols(
+        &self,
+    ) -> Result<rustls::ConfigBuilder<rustls::ServerConfig, rustls::WantsVerifier>, TlsConfigError>
+    {
+        let cipher_suites = match self.cipher_suites.is_empty() {
+            true => rustls::DEFAULT_CIPHER_SUITES.to_vec(),
+            false => self
+                .cipher_suites
+                .iter()
+                .map(|v| cipher_suite(v))
+                .collect::<Result<_, _>>()
+                .map_err(TlsConfigError::Format)?,
+        };
+        let kx_groups = match self.kx_groups.is_empty() {
+            true => rustls::ALL_KX_GROUPS.to_vec(),
+            false => self
+                .kx_groups
+                .iter()
+                .map(|v| kx_group(v))
+                .collect::<Result<_, _>>()
+                .map_err(TlsConfigError::Format)?,
+        };
+        rustls::ServerConfig::builder()
+            .with_cipher_suites(&cipher_suites)
+            .with_kx_groups(&kx_groups)
+            .with_protocol_versions(self.min_version.versions())
+            .map_err(|e| {
+                TlsConfigError::Format(format!(
+                    "tls {} or later with the selected cipher suites: {}",
+                    self.min_version, e
+                ))
+            })
+    }
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = match self.self_signed {
+            Some(_) => Vec::new(),
+            None => vec![
+                self.cert_path.to_path_buf(),
+                self.priv_key_path.to_path_buf(),
+            ],
+        };
+        for host in &self.hosts {
+            files.extend(host.https_cert.iter().cloned());
+            files.extend(host.https_key.iter().cloned());
+        }
+        for (_, ocsp) in self.staples() {
+            files.push(ocsp.to_path_buf());
+        }
+        if self.client_auth != ClientAuth::None {
+            files.extend(self.client_ca.iter().cloned());
+            files.extend(self.client_crl.iter().cloned());
+        }
+        files.sort();
+        files.dedup();
+        files
+    }
+    pub fn ocsp_update(&self, now: SystemTime) -> Option<SystemTime> {
+        self.staples()
+            .into_iter()
+            .filter_map(|(cert, ocsp)| {
+                let cert = load_cert(cert).ok()?;
+                let der = std::fs::read(ocsp).ok()?;
+                let issuer = cert.get(1).map(|v| v.0.as_slice());
+                OcspResponse::parse(&der, &cert[0].0, issuer)
+                    .ok()?
+                    .changes_at(now)
+            })
+            .min()
+    }
+    pub fn crl_update(&self) -> Option<SystemTime> {
+        let path = match (&self.client_auth, &self.client_crl) {
+            (ClientAuth::None, _) | (_, None) => return None,
+            (_, Some(v)) => v,
+        };
+        pem::read(path)
+            .ok()?
+            .into_iter()
+            .filter_map(|item| match item {
+                Item::Crl(v) => v.next_update,
+                _ => None,
+            })
+            .min()
+    }
+    fn staples(&self) -> Vec<(&Path, &Path)> {
+        let mut staples = Vec::new();
+        if let (None, Some(ocsp)) = (&self.self_signed, &self.ocsp_path) {
+            staples.push((self.cert_path.as_path(), ocsp.as_path()));
+        }
+        for host in &self.hosts {
+            if let (Some(cert), Some(ocsp)) = (&host.https_cert, &host.https_ocsp) {
+                staples.push((cert.as_path(), ocsp.as_path()));
+            }
+        }
+        staples
+    }
+    fn client_cas(
+        &self,
+    ) -> Result<(Vec<rustls::Certificate>, rustls::RootCertStore), TlsConfigError> {
+        let path = match &self.client_ca {
+            Some(v) => v,
+            None => {
+                return Err(TlsConfigError::Certificate(format!(
+                    "client-auth {} requires a client-ca bundle",
+                    self.client_auth
+                )))
+            }
+        };
+        let cas = load_cert(path)?;
+        let mut roots = rustls::RootCertStore::empty();
+        for ca in &cas {
+            roots.add(ca).map_err(|e| {
+                TlsConfigError::Certificate(format!(
+                    "{}: invalid ca certificate: {:?}",
+                    path.display(),
+                    e
+                ))
+            })?;
+        }
+        Ok((cas, roots))
+    }
+    fn client_verifier(&self) -> Result<Arc<dyn ClientCertVerifier>, TlsConfigError> {
+        let (cas, roots) = self.client_cas()?;
+        let crls = match &self.client_crl {
+            Some(v) => load_crls(v, &cas)?,
+            None => Vec::new(),
+        };
+        let verifier = match self.client_auth {
+            ClientAuth::Required => AllowAnyAuthenticatedClient::new(roots),
+            _ => AllowAnyAnonymousOrAuthenticatedClient::new(roots),
+        };
+        debug!(
+            "{} client authentication with {} ca certificate(s)",
+            self.client_auth,
+            cas.len()
+        );
+        Ok(Arc::new(CrlVerifier::new(verifier, crls)))
+    }
+}
+
+pub struct TlsConfig {
+    builder: TlsConfigBuilder,
+    current: Arc<rustls::ServerConfig>,
+    sessions: &'static Sessions,
+    modified: Vec<Option<SystemTime>>,
+    ocsp_update: Option<SystemTime>,
+    crl_update: Option<SystemTime>,
+    next_check: Instant,
+}
+
+impl TlsConfig {
+    pub fn new(builder: TlsConfigBuilder) -> Result<TlsConfig, TlsConfigError> {
+        let modified = modified(&builder.files());
+        let current = Arc::new(builder.build()?);
+        Ok(TlsConfig {
+            ocsp_update: builder.ocsp_update(SystemTime::now()),
+            crl_update: builder.crl_update(),
+            next_check: Instant::now() + builder.reload_interval,
+            sessions: Sessions::shared(builder.tickets, builder.session_cache),
+            builder,
+            current,
+            modified,
+        })
+    }
+    pub fn current(&self) -> Arc<rustls::ServerConfig> {
+        Arc::clone(&self.current)
     }
-    fn other(&self) -> Vec<CliOpt> {
-        self._other.to_vec()
+    pub fn stats(&self) -> Arc<SessionStats> {
+        Arc::clone(&self.sessions.stats)
+    }
+    pub fn reload(&mut self, now: Instant) -> Instant {
+        self.sessions.log(now);
+        if now < self.next_check {
+            return self.next_check;
+        }
+        self.next_check = now + self.builder.reload_interval;
+        let modified = modified(&self.builder.files());
+        let reason = if modified != self.modified {
+            "a file change"
+        } else if self.ocsp_update.is_some_and(|v| SystemTime::now() >= v) {
+            "an OCSP response validity change"
+        } else if self.crl_update.is_some_and(|v| SystemTime::now() >= v) {
+            "a CRL expiry"
+        } else {
+            return self.next_check;
+        };
+        self.modified = modified;
+        match self.builder.build() {
+            Ok(v) => {
+                self.current = Arc::new(v);
+                info!("reloaded tls certificates after {}", reason);
+            }
+            Err(e) => error!("keeping the current tls certificates, reload failed: {}", e),
+        }
+        self.ocsp_update = self.builder.ocsp_update(SystemTime::now());
+        self.crl_update = self.builder.crl_update();
+        self.next_check
     }
 }
 
-impl Default for TlsConfigBuilder {
-    fn default() -> Self {
-        TlsConfigBuilder {
-            https_enabled: false,
-            cert_path: PathBuf::new(),
-            priv_key_path: PathBuf::new(),
-            _other: Vec::new(),
+fn modified(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|v| std::fs::metadata(v).and_then(|v| v.modified()).ok())
+        .collect()
+}
+
+pub fn certified_key(
+    cert_path: &Path,
+    priv_key_path: &Path,
+    ocsp_path: Option<&Path>,
+    unlocker: &mut Unlocker,
+) -> Result<Arc<CertifiedKey>, TlsConfigError> {
+    let cert_chain = load_cert(cert_path)?;
+    let key = load_priv_key(priv_key_path, unlocker)?;
+    verify_pair(cert_path, &cert_chain, key.as_ref())?;
+    let ocsp = match ocsp_path {
+        Some(v) => load_ocsp(v, &cert_chain)?,
+        None => None,
+    };
+    let mut certified_key = CertifiedKey::new(cert_chain, key);
+    certified_key.ocsp = ocsp;
+    Ok(Arc::new(certified_key))
+}
+
+fn verify_pair(
+    path: &Path,
+    cert_chain: &[rustls::Certificate],
+    key: &dyn SigningKey,
+) -> Result<(), TlsConfigError> {
+    use rustls::SignatureScheme;
+
+    let cert = match cert_chain.first() {
+        Some(v) => webpki::EndEntityCert::try_from(v.0.as_ref()).map_err(|e| {
+            TlsConfigError::Certificate(format!("{}: invalid certificate: {:?}", path.display(), e))
+        })?,
+        None => {
+            return Err(TlsConfigError::Certificate(format!(
+                "no certificates found in {}",
+                path.display()
+            )))
         }
+    };
+    let signer = match key.choose_scheme(&[
+        SignatureScheme::ECDSA_NISTP256_SHA256,
+        SignatureScheme::ECDSA_NISTP384_SHA384,
+        SignatureScheme::ED25519,
+        SignatureScheme::RSA_PSS_SHA256,
+    ]) {
+        Some(v) => v,
+        None => {
+            return Err(TlsConfigError::PrivateKey(String::from(
+                "unsupported private key type",
+            )))
+        }
+    };
+    let algorithm = match signer.scheme() {
+        SignatureScheme::ECDSA_NISTP256_SHA256 => &webpki::ECDSA_P256_SHA256,
+        SignatureScheme::ECDSA_NISTP384_SHA384 => &webpki::ECDSA_P384_SHA384,
+        SignatureScheme::ED25519 => &webpki::ED25519,
+        _ => &webpki::RSA_PSS_2048_8192_SHA256_LEGACY_KEY,
+    };
+    let message = b"twebd certificate and private key check";
+    let signature = signer.sign(message)?;
+    match cert.verify_signature(algorithm, message, &signature) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(TlsConfigError::PrivateKey(format!(
+            "private key doesn't match the certificate {}",
+            path.display()
+        ))),
     }
 }
 
-fn load_priv_key(path: &PathBuf) -> Result<rustls::PrivateKey, TlsConfigError> {
-    if !path.exists() {
-        return Err(TlsConfigError::UnresolvablePath(
-            path.to_str().unwrap().to_string(),
-        ));
-    }
-    let handle = std::fs::File::open(path.canonicalize().unwrap())?;
-    let mut buf_reader = std::io::BufReader::new(handle);
-    match rustls_pemfile::pkcs8_private_keys(&mut buf_reader).map_err(|e| {
-        TlsConfigError::PrivateKey(format!("failed to load private key: {:?}: {:?}", path, e,))
-    }) {
-        Ok(priv_keys) => {
-            if priv_keys.len() == 1 {
-                debug!("loaded private key");
-                Ok(rustls::PrivateKey(priv_keys[0].to_vec()))
-            } else {
-                Err(TlsConfigError::PrivateKey(format!(
-                    "expected exactly one private key, got: {}",
-                    priv_keys.len()
-                )))
-            }
+fn load_priv_key(
+    path: &Path,
+    unlocker: &mut Unlocker,
+) -> Result<Arc<dyn SigningKey>, TlsConfigError> {
+    let mut keys: Vec<Arc<dyn SigningKey>> = Vec::new();
+    for item in pem::read(path)? {
+        match item {
+            Item::PrivateKey(v) => keys.push(passphrase::signing_key(v)?),
+            Item::EncryptedPrivateKey(v) => keys.push(unlocker.decrypt_key(path, &v)?),
+            _ => {}
         }
-        Err(e) => Err(e),
+    }
+    match keys.len() {
+        1 => {
+            debug!("loaded private key");
+            Ok(keys.remove(0))
+        }
+        n => Err(TlsConfigError::PrivateKey(format!(
+            "expected exactly one private key in {}, got: {}",
+            path.display(),
+            n
+        ))),
     }
 }
 
-fn load_cert(path: &PathBuf) -> Result<Vec<rustls::Certificate>, TlsConfigError> {
-    if !path.exists() {
-        return Err(TlsConfigError::UnresolvablePath(
-            path.to_str().unwrap().to_string(),
-        ));
+fn load_cert(path: &Path) -> Result<Vec<rustls::Certificate>, TlsConfigError> {
+    let certs: Vec<rustls::Certificate> = pem::read(path)?
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Certificate(v) => Some(v),
+            _ => None,
+        })
+        .collect();
+    if certs.is_empty() {
+        return Err(TlsConfigError::Certificate(format!(
+            "no certificates found in {}",
+            path.display()
+        )));
     }
-    let handle = std::fs::File::open(path.canonicalize().unwrap())?;
-    let mut buf_reader = std::io::BufReader::new(handle);
+    debug!("loaded {} certificate(s)", certs.len());
+    Ok(certs)
+}
 
-    match rustls_pemfile::certs(&mut buf_reader) {
+fn load_ocsp(
+    path: &Path,
+    cert_chain: &[rustls::Certificate],
+) -> Result<Option<Vec<u8>>, TlsConfigError> {
+    let der = match std::fs::read(path) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(TlsConfigError::UnresolvablePath(format!(
+                "{}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+    let issuer = cert_chain.get(1).map(|v| v.0.as_slice());
+    match OcspResponse::parse(&der, &cert_chain[0].0, issuer)
+        .and_then(|v| v.check(SystemTime::now()).map(|_| v))
+    {
         Ok(v) => {
-            debug!("loaded certificate(s)");
-            Ok(v.iter()
-                .map(|cert| rustls::Certificate(cert.to_vec()))
-                .collect::<Vec<rustls::Certificate>>())
-        }
-        Err(e) => Err(TlsConfigError::Certificate(format!(
-            "failed to load certificate or chain: {:?}: {:?}",
-            path, e
-        ))),
+            debug!("loaded OCSP response from {}", path.display());
+            Ok(Some(v.der))
+        }
+        Err(e) => {
+            warn!("not stapling the OCSP response {}: {}", path.display(), e);
+            Ok(None)
+        }
+    }
+}
+
+fn load_crls(path: &Path, cas: &[rustls::Certificate]) -> Result<Vec<Crl>, TlsConfigError> {
+    let crls: Vec<Crl> = pem::read(path)?
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Crl(v) => Some(v),
+            _ => None,
+        })
+        .collect();
+    if crls.is_empty() {
+        return Err(TlsConfigError::Certificate(format!(
+            "no CRLs found in {}",
+            path.display()
+        )));
+    }
+    for crl in &crls {
+        if !cas.iter().any(|ca| crl.is_issued_by(&ca.0)) {
+            return Err(TlsConfigError::Certificate(format!(
+                "{}: CRL is not signed by a certificate in the client-ca bundle",
+                path.display()
+            )));
+        }
+        crl.check(SystemTime::now())
+            .map_err(|e| TlsConfigError::Certificate(format!("{}: {}", path.display(), e)))?;
+    }
+    debug!(
+        "loaded {} CRL(s) revoking {} certificate(s)",
+        crls.len(),
+        crls.iter().map(|v| v.revoked.len()).sum::<usize>()
+    );
+    Ok(crls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::https::testdata::{path, read};
+
+    #[test]
+    fn expired_crls_keep_the_reload_scheduled() {
+        let mut config = ServerConfig::default();
+        let listener = config.listen[0].clone();
+        let builder = TlsConfigBuilder::new(&config, &listener);
+        assert_eq!(builder.crl_update(), None);
+
+        config.client_auth = ClientAuth::Required;
+        config.client_ca = Some(path("ca.pem"));
+        config.client_crl = Some(path("crl.pem"));
+        let builder = TlsConfigBuilder::new(&config, &listener);
+        let next_update = Crl::parse(&read("crl.der")).unwrap().next_update;
+        assert!(next_update.is_some());
+        assert_eq!(builder.crl_update(), next_update);
     }
 }
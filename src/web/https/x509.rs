@@ -0,0 +1,322 @@
+use crate::web::https::{
+    der::{elements, oid, time, tlv},
+    ocsp::CLOCK_SKEW,
+};
+use std::{
+    net::{Ipv4Addr, Ipv6Addr},
+    time::SystemTime,
+};
+
+const SUBJECT_ALT_NAME: &str = "2.5.29.17";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerCertificate {
+    pub subject: String,
+    pub issuer: String,
+    pub serial: String,
+    pub sans: Vec<String>,
+}
+
+impl PeerCertificate {
+    pub fn parse(der: &[u8]) -> Option<PeerCertificate> {
+        let tbs = Tbs::parse(der)?;
+        Some(PeerCertificate {
+            subject: name(tbs.subject)?,
+            issuer: name(tbs.issuer)?,
+            serial: hex(tbs.serial),
+            sans: match tbs.extensions {
+                Some(v) => sans(v)?,
+                None => Vec::new(),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Crl {
+    pub issuer: Vec<u8>,
+    pub revoked: Vec<Vec<u8>>,
+    pub this_update: SystemTime,
+    pub next_update: Option<SystemTime>,
+    tbs: Vec<u8>,
+    algorithm: String,
+    signature: Vec<u8>,
+}
+
+impl Crl {
+    pub fn parse(der: &[u8]) -> Option<Crl> {
+        let (tag, value, _) = tlv(der)?;
+        if tag != 0x30 {
+            return None;
+        }
+        let (tbs, algorithm, signature) = match elements(value)?.as_slice() {
+            [(0x30, _, tbs), (0x30, algorithm, _), (0x03, signature, _)] => {
+                (*tbs, *algorithm, signature.get(1..)?)
+            }
+            _ => return None,
+        };
+        let (_, fields, _) = tlv(tbs)?;
+        let fields = elements(fields)?;
+        let mut fields = fields.iter().skip_while(|(tag, _, _)| *tag == 0x02).skip(1);
+        let issuer = match fields.next()? {
+            (0x30, _, raw) => raw.to_vec(),
+            _ => return None,
+        };
+        let this_update = match fields.next()? {
+            (tag @ (0x17 | 0x18), v, _) => time(*tag, v)?,
+            _ => return None,
+        };
+        let mut fields = fields.peekable();
+        let next_update = match fields.peek() {
+            Some((tag @ (0x17 | 0x18), v, _)) => {
+                let v = time(*tag, v)?;
+                fields.next();
+                Some(v)
+            }
+            _ => None,
+        };
+        let mut revoked = Vec::new();
+        if let Some((_, entries, _)) = fields.find(|(tag, _, _)| *tag == 0x30) {
+            for (_, entry, _) in elements(entries)? {
+                match tlv(entry)? {
+                    (0x02, serial, _) => revoked.push(serial.to_vec()),
+                    _ => return None,
+                }
+            }
+        }
+        Some(Crl {
+            issuer,
+            revoked,
+            this_update,
+            next_update,
+            tbs: tbs.to_vec(),
+            algorithm: match tlv(algorithm)? {
+                (0x06, v, _) => oid(v),
+                _ => return None,
+            },
+            signature: signature.to_vec(),
+        })
+    }
+    pub fn is_issued_by(&self, ca: &[u8]) -> bool {
+        let cert = match (Tbs::parse(ca), webpki::EndEntityCert::try_from(ca)) {
+            (Some(tbs), Ok(cert)) if tbs.subject == self.issuer.as_slice() => cert,
+            _ => return false,
+        };
+        algorithms(&self.algorithm).iter().any(|algorithm| {
+            cert.verify_signature(algorithm, &self.tbs, &self.signature)
+                .is_ok()
+        })
+    }
+    pub fn check(&self, now: SystemTime) -> Result<(), String> {
+        if self.this_update > now + CLOCK_SKEW {
+            Err(String::from("CRL is not valid yet"))
+        } else if self.next_update.is_some_and(|v| v <= now) {
+            Err(String::from(
+                "CRL has expired, fetch a current one from the CA",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+    pub fn is_revoked(&self, cert: &[u8]) -> bool {
+        match Tbs::parse(cert) {
+            Some(tbs) => {
+                tbs.issuer == self.issuer.as_slice()
+                    && self.revoked.iter().any(|v| v.as_slice() == tbs.serial)
+            }
+            None => false,
+        }
+    }
+}
+
+pub struct Tbs<'a> {
+    pub serial: &'a [u8],
+    pub issuer: &'a [u8],
+    pub subject: &'a [u8],
+    pub public_key: &'a [u8],
+    pub extensions: Option<&'a [u8]>,
+}
+
+impl<'a> Tbs<'a> {
+    pub fn parse(der: &'a [u8]) -> Option<Tbs<'a>> {
+        let (_, cert, _) = tlv(der)?;
+        let (_, tbs, _) = tlv(cert)?;
+        let fields = elements(tbs)?;
+        let fields = match fields.first()? {
+            (0xa0, _, _) => &fields[1..],
+            _ => &fields[..],
+        };
+        match fields {
+            [(0x02, serial, _), _, (0x30, _, issuer), _, (0x30, _, subject), (0x30, spki, _), rest @ ..] => {
+                Some(Tbs {
+                    serial,
+                    issuer,
+                    subject,
+                    public_key: match elements(spki)?.as_slice() {
+                        [(0x30, _, _), (0x03, key, _)] => key.get(1..)?,
+                        _ => return None,
+                    },
+                    extensions: rest
+                        .iter()
+                        .find(|(tag, _, _)| *tag == 0xa3)
+                        .map(|(_, v, _)| *v),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn name(raw: &[u8]) -> Option<String> {
+    let (_, rdns, _) = tlv(raw)?;
+    let mut parts = Vec::new();
+    for (_, rdn, _) in elements(rdns)? {
+        for (_, attribute, _) in elements(rdn)? {
+            let (key, value) = match elements(attribute)?.as_slice() {
+                [(0x06, key, _), (tag, value, _)] => (oid(key), string(*tag, value)),
+                _ => return None,
+            };
+            let key = match key.as_str() {
+                "2.5.4.3" => "CN",
+                "2.5.4.6" => "C",
+                "2.5.4.7" => "L",
+                "2.5.4.8" => "ST",
+                "2.5.4.10" => "O",
+                "2.5.4.11" => "OU",
+                "1.2.840.113549.1.9.1" => "emailAddress",
+                v => v,
+            };
+            parts.push(format!("{}={}", key, value));
+        }
+    }
+    Some(parts.join(", "))
+}
+
+fn string(tag: u8, value: &[u8]) -> String {
+    match tag {
+        0x1e => char::decode_utf16(
+            value
+                .chunks(2)
+                .map(|v| u16::from_be_bytes([v[0], *v.get(1).unwrap_or(&0)])),
+        )
+        .map(|v| v.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect(),
+        _ => String::from_utf8_lossy(value).into_owned(),
+    }
+}
+
+fn sans(extensions: &[u8]) -> Option<Vec<String>> {
+    let (_, extensions, _) = tlv(extensions)?;
+    let mut sans = Vec::new();
+    for (_, extension, _) in elements(extensions)? {
+        let fields = elements(extension)?;
+        match (fields.first()?, fields.last()?) {
+            ((0x06, id, _), (0x04, value, _)) if oid(id) == SUBJECT_ALT_NAME => {
+                let (_, names, _) = tlv(value)?;
+                for (tag, value, _) in elements(names)? {
+                    let value = match (tag, value.len()) {
+                        (0x81, _) => format!("email:{}", String::from_utf8_lossy(value)),
+                        (0x82, _) => format!("DNS:{}", String::from_utf8_lossy(value)),
+                        (0x86, _) => format!("URI:{}", String::from_utf8_lossy(value)),
+                        (0x87, 4) => {
+                            format!("IP:{}", Ipv4Addr::from(<[u8; 4]>::try_from(value).ok()?))
+                        }
+                        (0x87, 16) => {
+                            format!("IP:{}", Ipv6Addr::from(<[u8; 16]>::try_from(value).ok()?))
+                        }
+                        _ => continue,
+                    };
+                    sans.push(value);
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(sans)
+}
+
+fn algorithms(oid: &str) -> Vec<&'static webpki::SignatureAlgorithm> {
+    match oid {
+        "1.2.840.113549.1.1.11" => vec![&webpki::RSA_PKCS1_2048_8192_SHA256],
+        "1.2.840.113549.1.1.12" => vec![&webpki::RSA_PKCS1_2048_8192_SHA384],
+        "1.2.840.113549.1.1.13" => vec![&webpki::RSA_PKCS1_2048_8192_SHA512],
+        "1.2.840.10045.4.3.2" => vec![&webpki::ECDSA_P256_SHA256, &webpki::ECDSA_P384_SHA256],
+        "1.2.840.10045.4.3.3" => vec![&webpki::ECDSA_P384_SHA384, &webpki::ECDSA_P256_SHA384],
+        "1.3.101.112" => vec![&webpki::ED25519],
+        _ => Vec::new(),
+    }
+}
+
+fn hex(serial: &[u8]) -> String {
+    let serial = match serial {
+        [0, rest @ ..] if !rest.is_empty() => rest,
+        v => v,
+    };
+    serial.iter().map(|v| format!("{:02X}", v)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::https::testdata::{cert, read};
+    use std::time::Duration;
+
+    #[test]
+    fn peer_certificate_fields() {
+        let cert = PeerCertificate::parse(&read("leaf.der")).unwrap();
+        assert_eq!(cert.subject, "O=twebd, CN=localhost");
+        assert_eq!(cert.issuer, "CN=twebd test ca");
+        assert_eq!(cert.serial, "1001");
+        assert_eq!(cert.sans, vec!["DNS:localhost", "IP:127.0.0.1"]);
+    }
+
+    #[test]
+    fn crl_is_signed_by_the_ca() {
+        let crl = Crl::parse(&read("crl.der")).unwrap();
+        assert!(crl.is_issued_by(&cert("ca.pem")));
+        assert!(!crl.is_issued_by(&cert("leaf.pem")));
+    }
+
+    #[test]
+    fn crl_revokes_listed_serials() {
+        let crl = Crl::parse(&read("crl.der")).unwrap();
+        assert_eq!(crl.revoked, vec![vec![0x10, 0x02]]);
+        assert!(crl.is_revoked(&cert("revoked.pem")));
+        assert!(!crl.is_revoked(&cert("leaf.pem")));
+        assert!(!crl.is_revoked(b"not a certificate"));
+    }
+
+    #[test]
+    fn crl_validity_period() {
+        let crl = Crl::parse(&read("crl.der")).unwrap();
+        let next_update = crl.next_update.unwrap();
+        assert_eq!(
+            next_update.duration_since(crl.this_update).unwrap(),
+            Duration::from_secs(30 * 24 * 60 * 60)
+        );
+        assert!(crl.check(crl.this_update).is_ok());
+        assert!(crl.check(next_update - Duration::from_secs(1)).is_ok());
+        assert!(crl.check(crl.this_update - CLOCK_SKEW).is_ok());
+        assert!(crl.check(next_update).is_err());
+        assert!(crl.check(crl.this_update - CLOCK_SKEW * 2).is_err());
+    }
+
+    #[test]
+    fn truncated_crl_is_rejected() {
+        let der = read("crl.der");
+        for len in 0..der.len() {
+            assert!(Crl::parse(&der[..len]).is_none(), "parsed {} bytes", len);
+        }
+    }
+
+    #[test]
+    fn malformed_crl_is_rejected() {
+        let der = read("crl.der");
+        let mut wrong_tag = der.clone();
+        wrong_tag[0] = 0x31;
+        assert!(Crl::parse(&wrong_tag).is_none());
+        assert!(Crl::parse(&read("leaf.der")).is_none());
+        assert!(Crl::parse(&[0x30, 0x84, 0xff, 0xff, 0xff, 0xff]).is_none());
+        assert!(Crl::parse(&[0x30, 0x00]).is_none());
+    }
+}
@@ -6,10 +6,21 @@ pub use http::{
     adapter::{HttpAdapter, HttpReceiver, HttpResponder},
     delim,
     err::{HttpParseError, HttpResponseError},
-    interop::{stringify, tokenize_s, HttpParser, ToBuffer, TokenIter},
+    interop::{content_length, header_len, stringify, tokenize_s, HttpParser, ToBuffer, TokenIter},
     native::{
         HttpBody, HttpHeader, HttpLine, HttpMethod, HttpRequest, HttpResponse, HttpResponseLine,
         HttpStatus, HttpVersion,
     },
 };
-pub use https::{err::TlsConfigError, tls::TlsConfigBuilder};
+pub use https::{
+    client::{ClientAuth, CrlVerifier},
+    err::TlsConfigError,
+    ocsp::{CertStatus, OcspResponse},
+    params::TlsVersion,
+    passphrase::Passphrase,
+    selfsigned::SelfSigned,
+    session::{SessionSnapshot, SessionStats, Sessions},
+    sni::SniResolver,
+    tls::{TlsConfig, TlsConfigBuilder},
+    x509::{Crl, PeerCertificate},
+};
@@ -0,0 +1,51 @@
+#!/bin/sh
+# Regenerates the certificate, CRL, OCSP and key fixtures used by the unit tests.
+set -e
+cd "$(dirname "$0")"
+work=$(mktemp -d)
+trap 'rm -rf "$work"' EXIT
+
+openssl ecparam -name prime256v1 -genkey -noout -out ca.key
+openssl req -x509 -new -key ca.key -subj "/CN=twebd test ca" -days 3650 -out ca.pem
+openssl ecparam -name prime256v1 -genkey -noout -out "$work/other.key"
+openssl req -x509 -new -key "$work/other.key" -subj "/CN=twebd test ca" -days 3650 -out other-ca.pem
+
+openssl ecparam -name prime256v1 -genkey -noout -out sec1.pem
+openssl ec -in sec1.pem -outform der -out sec1.der
+openssl pkcs8 -topk8 -nocrypt -in sec1.pem -out pkcs8.pem
+openssl pkcs8 -topk8 -nocrypt -in sec1.pem -outform der -out pkcs8.der
+openssl pkcs8 -topk8 -v2 aes-256-cbc -passout pass:s3cret -in sec1.pem -out encrypted.pem
+openssl genrsa -traditional -out rsa.pem 2048
+
+printf 'subjectAltName=DNS:localhost,IP:127.0.0.1\n' > "$work/ext.cnf"
+openssl req -new -key sec1.pem -subj "/O=twebd/CN=localhost" -out "$work/leaf.csr"
+openssl x509 -req -in "$work/leaf.csr" -CA ca.pem -CAkey ca.key -set_serial 0x1001 \
+    -days 3650 -extfile "$work/ext.cnf" -out leaf.pem
+openssl x509 -in leaf.pem -outform der -out leaf.der
+openssl x509 -req -in "$work/leaf.csr" -CA ca.pem -CAkey ca.key -set_serial 0x1002 \
+    -days 3650 -out revoked.pem
+
+cat > "$work/ca.cnf" <<CNF
+[ca]
+default_ca = test
+[test]
+database = $work/index.txt
+crlnumber = $work/crlnumber
+default_md = sha256
+default_crl_days = 30
+CNF
+: > "$work/index.txt"
+echo 01 > "$work/crlnumber"
+openssl ca -config "$work/ca.cnf" -cert ca.pem -keyfile ca.key -revoke revoked.pem
+openssl ca -config "$work/ca.cnf" -cert ca.pem -keyfile ca.key -gencrl -out crl.pem
+openssl crl -in crl.pem -outform der -out crl.der
+
+printf 'V\t300101000000Z\t\t1001\tunknown\t/O=twebd/CN=localhost\n' >> "$work/index.txt"
+for name in leaf revoked; do
+    openssl ocsp -issuer ca.pem -cert $name.pem -no_nonce -reqout "$work/$name.req"
+    openssl ocsp -index "$work/index.txt" -CA ca.pem -rsigner ca.pem -rkey ca.key \
+        -reqin "$work/$name.req" -ndays 7 -respout ocsp-$name.der
+done
+
+cat leaf.pem ca.pem pkcs8.pem crl.pem > bundle.pem
+rm ca.key